@@ -1,12 +1,15 @@
-use shortpaths::{
-    shortpaths::{Shortpath, SP},
+use shortpath::{
+    shortpaths::{Shortpath, SP, expand_shortpath, expand_shortpaths},
     builder::{ShortpathsBuilder, ShortpathOperationsExt},
-    export::{Export, bash::BashExporter},
+    export::{ShellExporter, bash::BashExporter},
+    helpers::{search_for, matching_file_names, in_search_roots, walk_dir},
+    resolve::ResolveConfig,
 };
 
-use std::path::PathBuf;
+use std::{fs::create_dir_all, path::PathBuf};
 
 use indexmap::indexmap;
+use tempfile::TempDir;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 // Benchmarks
@@ -15,10 +18,63 @@ fn bench_populdate_expanded_paths(shortpaths: &SP) -> SP {
     shortpaths.populate_expanded_paths()
 }
 
+/// Expand every shortpath on its own, re-expanding the shared aliases every time
+fn bench_expand_each(shortpaths: &SP) -> usize {
+    shortpaths.values().map(|sp| expand_shortpath(sp, shortpaths).unwrap().as_os_str().len()).sum()
+}
+
+/// Expand every shortpath at once, reusing the expanded aliases
+fn bench_expand_all(shortpaths: &SP) -> usize {
+    expand_shortpaths(shortpaths).values().flatten().map(|path| path.as_os_str().len()).sum()
+}
+
 fn bench_nested_serialize_bash(shortpaths: &SP) -> String {
-    let exp = BashExporter::default()
-        .set_shortpaths(shortpaths);
-    exp.gen_completions()
+    let exp = BashExporter;
+    exp.gen_completions(shortpaths.to_owned())
+}
+
+fn bench_search_for(unreachable: &SP, cfg: &ResolveConfig) -> usize {
+    search_for(matching_file_names, in_search_roots, unreachable, cfg).len()
+}
+
+/// Walk the search roots of every shortpath one after another, as before the shared index
+fn bench_search_each_sequential(unreachable: &SP, cfg: &ResolveConfig) -> usize {
+    unreachable.values().map(|sp| {
+        in_search_roots(sp, cfg).into_iter().map(|(dir, max_depth)| {
+            let files: Vec<_> = walk_dir(&dir, max_depth, cfg).collect();
+            matching_file_names(sp, &files).len()
+        }).sum::<usize>()
+    }).sum()
+}
+
+/// Generate a config of nested aliases, in chains of the given depth
+fn setup_alias_chains(entries: usize, depth: usize) -> SP {
+    (0..entries).map(|i| {
+        let path = match i % depth {
+            0 => format!("/base/chain{}", i / depth),
+            _ => format!("$sp{}/dir{}", i - 1, i),
+        };
+        (format!("sp{i}"), Shortpath::new(PathBuf::from(path), None))
+    }).collect()
+}
+
+/// Create search roots full of directories, and shortpaths to directories that moved into them
+fn setup_moved_dirs(roots: usize, dirs: usize) -> (TempDir, SP, ResolveConfig) {
+    let dir = tempfile::Builder::new().prefix("shortpath-bench-search-").tempdir().unwrap();
+    let base = dir.path();
+
+    let mut unreachable = SP::new();
+    for root in 0..roots {
+        for dir in 0..dirs {
+            create_dir_all(base.join(format!("root{root}/group{}/dir{root}-{dir}", dir % 10))).unwrap();
+        }
+        let path = base.join(format!("old/dir{root}-0"));
+        unreachable.insert(format!("dir{root}"), Shortpath::new(path.clone(), Some(path)));
+    }
+
+    let roots = (0..roots).map(|root| base.join(format!("root{root}"))).collect();
+    let cfg = ResolveConfig { roots, trash_dir: Some(base.join("Trash")), ..Default::default() };
+    (dir, unreachable, cfg)
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -44,6 +100,18 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     c.bench_function("bench_nested_serialize_bash",
         |b| b.iter(|| bench_nested_serialize_bash(black_box(&shortpaths))));
+
+    let chains = setup_alias_chains(10_000, 100);
+    c.bench_function("bench_expand_each_10k",
+        |b| b.iter(|| bench_expand_each(black_box(&chains))));
+    c.bench_function("bench_expand_all_10k",
+        |b| b.iter(|| bench_expand_all(black_box(&chains))));
+
+    let (_dir, unreachable, cfg) = setup_moved_dirs(8, 500);
+    c.bench_function("bench_search_each_sequential",
+        |b| b.iter(|| bench_search_each_sequential(black_box(&unreachable), black_box(&cfg))));
+    c.bench_function("bench_search_for",
+        |b| b.iter(|| bench_search_for(black_box(&unreachable), black_box(&cfg))));
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,74 @@
+# Shortpath hooks for PowerShell
+# Wraps Move-Item and Remove-Item (and their mv, rm aliases) to keep the shortpaths config up to date
+# The real cmdlets always run, and only the items they moved or removed are passed to the shortpath hooks
+
+# Resolve wildcards and literal paths to the full paths of the existing items
+function Resolve-ShortpathItem {
+    param([string[]] $Path, [string[]] $LiteralPath)
+    if ($LiteralPath) {
+        $resolved = Resolve-Path -LiteralPath $LiteralPath -ErrorAction SilentlyContinue
+    } else {
+        $resolved = Resolve-Path -Path $Path -ErrorAction SilentlyContinue
+    }
+    $resolved | ForEach-Object { $_.ProviderPath }
+}
+
+# Find and update any entry in the shortpaths config containing the moved items
+function Move-Item {
+    [CmdletBinding(SupportsShouldProcess, DefaultParameterSetName = 'Path')]
+    param(
+        [Parameter(Mandatory, Position = 0, ValueFromPipeline, ValueFromPipelineByPropertyName, ParameterSetName = 'Path')]
+        [string[]] $Path,
+        [Parameter(Mandatory, ValueFromPipelineByPropertyName, ParameterSetName = 'LiteralPath')]
+        [Alias('PSPath', 'LP')]
+        [string[]] $LiteralPath,
+        [Parameter(Position = 1, ValueFromPipelineByPropertyName)]
+        [string] $Destination = '.',
+        [string] $Filter,
+        [string[]] $Include,
+        [string[]] $Exclude,
+        [switch] $Force,
+        [switch] $PassThru
+    )
+    process {
+        $sources = @(Resolve-ShortpathItem -Path $Path -LiteralPath $LiteralPath)
+        $target = $ExecutionContext.SessionState.Path.GetUnresolvedProviderPathFromPSPath($Destination)
+        $intoDirectory = Test-Path -LiteralPath $target -PathType Container
+
+        Microsoft.PowerShell.Management\Move-Item @PSBoundParameters
+
+        foreach ($source in $sources) {
+            $moved = if ($intoDirectory) { Join-Path $target (Split-Path $source -Leaf) } else { $target }
+            if (-not (Test-Path -LiteralPath $source) -and (Test-Path -LiteralPath $moved)) {
+                shortpath -v hook move -- $source $moved
+            }
+        }
+    }
+}
+
+# Find and remove any entry in the shortpaths config containing the removed items
+function Remove-Item {
+    [CmdletBinding(SupportsShouldProcess, DefaultParameterSetName = 'Path')]
+    param(
+        [Parameter(Mandatory, Position = 0, ValueFromPipeline, ValueFromPipelineByPropertyName, ParameterSetName = 'Path')]
+        [string[]] $Path,
+        [Parameter(Mandatory, ValueFromPipelineByPropertyName, ParameterSetName = 'LiteralPath')]
+        [Alias('PSPath', 'LP')]
+        [string[]] $LiteralPath,
+        [string] $Filter,
+        [string[]] $Include,
+        [string[]] $Exclude,
+        [switch] $Recurse,
+        [switch] $Force
+    )
+    process {
+        $targets = @(Resolve-ShortpathItem -Path $Path -LiteralPath $LiteralPath)
+
+        Microsoft.PowerShell.Management\Remove-Item @PSBoundParameters
+
+        $removed = @($targets | Where-Object { -not (Test-Path -LiteralPath $_) })
+        if ($removed) {
+            shortpath hook remove -- @removed
+        }
+    }
+}
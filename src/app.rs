@@ -5,13 +5,13 @@ use std::{
 
 use crate::consts::PROGRAM_DESCRIPTION;
 
-use clap::{Parser, arg, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use log::{Level, LevelFilter};
 use env_logger::fmt::{Color, Style, StyledValue};
 
 // Custom Log Format
 
-fn colored_level(style: &mut Style, level: Level) -> StyledValue<&str> {
+fn colored_level(style: &mut Style, level: Level) -> StyledValue<'_, &str> {
     match level {
         Level::Trace => style.set_color(Color::Magenta).value("TRACE"),
         Level::Debug => style.set_color(Color::Blue).value("DEBUG"),
@@ -107,6 +107,12 @@ pub enum Commands {
         name: String,
         #[arg(value_name = "PATH")]
         path: PathBuf,
+
+        #[arg(long, default_value_t = false, help = "Allow names that shadow environment variables or can't be exported")]
+        force: bool,
+
+        #[arg(long, default_value_t = false, help = "Fold the path into the most specific shortpath alias or environment variable")]
+        fold: bool,
     },
 
     #[command(about = "Remove a shortpath")]
@@ -125,7 +131,28 @@ pub enum Commands {
     },
 
     #[command(about = "Checks all shortpaths")]
-    Check   {},
+    Check   {
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Text, help = "Output format of the check report")]
+        format: ReportFormat,
+    },
+
+    #[command(about = "Fold a path into the most specific shortpath alias or environment variable")]
+    Fold    {
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+    },
+
+    #[command(about = "Lints shortpath names and paths")]
+    Lint    {
+        #[arg(long, default_value_t = false, help = "Fix the lints that can be fixed automatically")]
+        fix: bool,
+    },
+
+    #[command(about = "Suggest base aliases for shortpaths that share a directory")]
+    Refactor {
+        #[arg(short, default_value_t = false, help = "Skip confirmation")]
+        yes: bool,
+    },
 
     #[command(about = "Fixes all shortpaths.")]
     Resolve {
@@ -137,6 +164,15 @@ pub enum Commands {
 
         #[arg(short, long, default_value_t = false, help = "Show shortpath config changes, but do not execute them")]
         dry_run: bool,
+
+        #[arg(short, long, value_enum, default_value_t = PlanFormat::Table, help = "Output format of the resolve plan")]
+        format: PlanFormat,
+
+        #[arg(short, long, value_name = "PLAN", help = "Apply a previously reviewed resolve plan")]
+        apply: Option<PathBuf>,
+
+        #[arg(long, default_value_t = false, help = "In automatic mode, also rewrite literal paths under moved shortpaths to use their alias (e.g. /old/proj/src to $proj/src)")]
+        rewrite_descendants: bool,
     },
 
     #[command(about = "Export shortpaths to other applications")]
@@ -145,6 +181,14 @@ pub enum Commands {
         export_type: ExportType,
         #[arg(short, long, help = "Output to file")]
         output_file: Option<PathBuf>,
+        #[arg(long, help = "Also export commands that jump to each shortpath (nushell)")]
+        jump: bool,
+        #[arg(long, help = "Include metadata about the export (json, yaml, dotenv)")]
+        metadata: bool,
+        #[arg(long, help = "Match names like Music to XDG user directories (user-dirs)")]
+        ignore_case: bool,
+        #[arg(short, long, conflicts_with = "output_file", help = "Install to the user's shell completions directory")]
+        install: bool,
     },
 
     #[command(about = "Update a shortpath")]
@@ -158,6 +202,9 @@ pub enum Commands {
         #[arg(value_name = "PATH")]
         #[arg(short, long, help = "New shortpath path")]
         path: Option<PathBuf>,
+
+        #[arg(long, default_value_t = false, help = "Allow names that shadow environment variables or can't be exported")]
+        force: bool,
     },
 
     #[command(about = "Hooks for running shortpath automatically")]
@@ -187,6 +234,17 @@ pub enum Hooks {
 pub enum ExportType {
     Bash,
     Powershell,
+    Fish,
+    Nushell,
+    Zsh,
+    Json,
+    Yaml,
+    Dotenv,
+    EnvironmentD,
+    Pam,
+    Direnv,
+    GtkBookmarks,
+    UserDirs,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -200,6 +258,19 @@ pub enum ResolveType {
     Matching
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum PlanFormat {
+    Table,
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum HookType {
     Move,
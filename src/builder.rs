@@ -1,22 +1,36 @@
 use crate::{
-    shortpaths::{SP, Shortpath, expand_shortpath},
+    shortpaths::{
+        SP, Shortpath, ReverseDeps, alias_chain, expand_shortpaths, fold_shortpath_for,
+        get_alias_dependency, join_alias_base, reverse_dependencies,
+    },
     config::Config,
+    resolve::ResolveConfig,
+    git::Repos,
     helpers::{expand_tilde, find_longest_keyname, tab_align, sort_shortpaths},
     env::EnvVars,
 };
 
 use std::path::PathBuf;
 
+use indexmap::{IndexMap, IndexSet};
 use serde::{Serialize, Deserialize};
 use log::{trace, info, debug};
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Shortpaths {
     pub shortpaths: SP,
+    #[serde(default, skip_serializing_if = "ResolveConfig::is_default")]
+    pub resolve: ResolveConfig,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub repos: Repos,
     #[serde(skip)]
     pub env_vars: Option<EnvVars>,
     #[serde(skip)]
     pub cfg: Option<Config>,
+    /// The shortpaths that directly depend on each alias
+    /// Kept up to date by `set_shortpaths`, `update_path` and `remove_path`
+    #[serde(skip)]
+    pub reverse_deps: ReverseDeps,
 }
 
 #[derive(Default, Debug)]
@@ -70,19 +84,7 @@ impl ShortpathsAlignExt for Shortpaths {
     fn fold_env_paths(self) -> Shortpaths {
         let evars = self.env_vars.unwrap();
         let shortpaths: SP = self.shortpaths.into_iter().map(|(name, mut sp)| {
-            let mut path = sp.path.to_str().unwrap().to_owned();
-            evars.vars.iter().for_each(|(envname, envpath)| {
-                if envname != &name {
-                    debug!("envname: {}", envname);
-                    debug!("envpath: {}", envpath);
-                    if !envpath.is_empty() && path.contains(envpath) {
-                        let this = envpath;
-                        let with = format!("${{env:{}}}", envname);
-                        path = path.replace(this, &with);
-                    }
-                }
-            });
-            sp.path = PathBuf::from(path);
+            sp.path = fold_shortpath_for(Some(&name), sp.path, &SP::new(), Some(&evars.vars));
             (name, sp)
         }).collect();
 
@@ -111,10 +113,15 @@ impl ShortpathOperationsExt for SP {
 
     fn populate_expanded_paths(&self) -> SP {
         info!("populate_expanded_paths()");
+        let expanded = expand_shortpaths(self);
         self.iter().map(|(k, sp)| {
-            let full_path = expand_shortpath(sp, self);
+            // Shortpaths with undefined or cyclic aliases are left unexpanded
+            let full_path = expanded[k].to_owned().unwrap_or_else(|| {
+                debug!("Could not expand {}", k);
+                sp.path.to_owned()
+            });
             let shortpath = Shortpath{ full_path: Some(full_path), ..sp.to_owned()};
-            info!("Final Shortpath {:?}", shortpath);
+            trace!("Final Shortpath {:?}", shortpath);
             (k.to_owned(), shortpath)
         }).collect()
     }
@@ -129,9 +136,100 @@ impl ShortpathOperationsExt for SP {
     }
 }
 
+impl Shortpaths {
+    /// Replace every shortpath, and rebuild the reverse dependency graph
+    pub fn set_shortpaths(&mut self, shortpaths: SP) {
+        self.reverse_deps = reverse_dependencies(&shortpaths);
+        self.shortpaths = shortpaths;
+    }
+
+    /// Get every shortpath that depends on a shortpath, directly or through other aliases
+    /// Shortpaths are returned before the shortpaths that depend on them
+    pub fn dependents(&self, name: &str) -> IndexSet<String> {
+        let mut dependents: IndexSet<String> = IndexSet::new();
+        let mut i = 0;
+        let mut next = Some(name);
+        while let Some(name) = next {
+            if let Some(direct) = self.reverse_deps.get(name) {
+                dependents.extend(direct.iter().cloned());
+            }
+            next = dependents.get_index(i).map(String::as_str);
+            i += 1;
+        }
+        dependents.shift_remove(name);
+        dependents
+    }
+
+    /** Re-expand a shortpath and every shortpath that depends on it
+      *
+      * Every shortpath depends on at most one alias, so they are all expandable
+      * only if the chain of aliases of the first shortpath is.
+      * Returns the names of the re-expanded shortpaths.
+      */
+    fn reexpand(&mut self, name: &str) -> Vec<String> {
+        let expandable = self.shortpaths.contains_key(name) && alias_chain(name, &self.shortpaths).is_ok();
+        let mut names: Vec<String> = vec![name.to_owned()];
+        names.extend(self.dependents(name));
+        names.retain(|name| self.shortpaths.contains_key(name));
+
+        for name in names.iter() {
+            let sp = &self.shortpaths[name];
+            let base = get_alias_dependency(sp)
+                .filter(|_| expandable)
+                .and_then(|alias| self.shortpaths.get(&alias)?.full_path.to_owned());
+            let full_path = match base {
+                Some(base) => join_alias_base(base, &sp.path),
+                None => expand_tilde(&sp.path).unwrap_or(sp.path.to_owned()),
+            };
+            trace!("Re-expanded {}: {}", name, full_path.display());
+            self.shortpaths[name].full_path = Some(full_path);
+        }
+        names
+    }
+
+    /** Add or update the path of a single shortpath
+      *
+      * Only the shortpath and the shortpaths that depend on it are re-expanded,
+      * instead of rebuilding every shortpath.
+      * Returns the names of the re-expanded shortpaths.
+      */
+    pub fn update_path(&mut self, name: &str, path: PathBuf) -> Vec<String> {
+        info!("update_path()");
+        if let Some(alias) = self.shortpaths.get(name).and_then(get_alias_dependency) {
+            if let Some(dependents) = self.reverse_deps.get_mut(&alias) {
+                dependents.shift_remove(name);
+            }
+        }
+        let sp = Shortpath::new(path, None);
+        if let Some(alias) = get_alias_dependency(&sp) {
+            self.reverse_deps.entry(alias).or_default().insert(name.to_owned());
+        }
+        self.shortpaths.insert(name.to_owned(), sp);
+        self.reexpand(name)
+    }
+
+    /** Remove a single shortpath
+      *
+      * The shortpaths that depend on it are left unexpanded.
+      * Returns the removed shortpath and the names of the re-expanded shortpaths.
+      */
+    pub fn remove_path(&mut self, name: &str) -> (Option<Shortpath>, Vec<String>) {
+        info!("remove_path()");
+        let removed = self.shortpaths.shift_remove(name);
+        if let Some(alias) = removed.as_ref().and_then(get_alias_dependency) {
+            if let Some(dependents) = self.reverse_deps.get_mut(&alias) {
+                dependents.shift_remove(name);
+            }
+        }
+        let mut reexpanded = self.reexpand(name);
+        reexpanded.retain(|reexpanded| reexpanded != name);
+        (removed, reexpanded)
+    }
+}
+
 impl From<SP> for ShortpathsBuilder {
     fn from(item: SP) -> Self {
-        let shortpaths = Shortpaths { shortpaths: item, cfg: None, env_vars: None};
+        let shortpaths = Shortpaths { shortpaths: item, ..Default::default() };
         ShortpathsBuilder { paths: Some(shortpaths), cfg: None }
     }
 }
@@ -146,7 +244,9 @@ impl ShortpathsBuilder {
                 .expand_special_characters()
                 .sort_paths_inplace();
             let env_vars = Default::default();
-            let paths = Shortpaths { shortpaths, cfg: self.cfg, env_vars: Some(env_vars)};
+            let (resolve, repos) = (paths.resolve.to_owned(), paths.repos.to_owned());
+            let reverse_deps = reverse_dependencies(&shortpaths);
+            let paths = Shortpaths { shortpaths, resolve, repos, cfg: self.cfg, env_vars: Some(env_vars), reverse_deps };
             return Some(paths);
         }
         None
@@ -169,11 +269,11 @@ impl ShortpathsBuilder {
     }
 
     pub fn shortpath(mut self, key: impl Into<String>, path: impl Into<String>) -> Self {
-        let mut shortpaths = self.paths.unwrap().shortpaths;
+        let mut paths = self.paths.unwrap_or_default();
         let path = PathBuf::from(path.into());
         let sp = Shortpath::new(path, None);
-        shortpaths.insert(key.into(), sp);
-        self.paths = Some(Shortpaths { shortpaths, cfg: None, env_vars: None});
+        paths.shortpaths.insert(key.into(), sp);
+        self.paths = Some(paths);
         self
     }
 }
@@ -0,0 +1,294 @@
+use crate::{
+    app::ReportFormat,
+    consts::{PROGRAM_NAME, VERSION},
+    escape::file_uri,
+    resolve::ResolveConfig,
+    shortpaths::{SP, AliasError, alias_chain, get_alias_dependency},
+    trash::{read_trash, find_trashed},
+};
+
+use std::{
+    fmt,
+    fs::{read_dir, symlink_metadata},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use log::{debug, info};
+use serde::Serialize;
+use serde_json::json;
+
+// Data Types
+
+/// How serious a finding is, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// The kinds of problems `check` can find
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    Unreachable,
+    UndefinedAlias,
+    Cycle,
+    TypeMismatch,
+    PermissionDenied,
+    DanglingSymlink,
+    DuplicateTarget,
+}
+
+/// A single problem with a shortpath
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// The shortpath alias name
+    pub name: String,
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Every problem found by `check`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CheckReport {
+    pub findings: Vec<Finding>,
+}
+
+// Trait Implementations
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FindingKind::Unreachable => write!(f, "unreachable"),
+            FindingKind::UndefinedAlias => write!(f, "undefined_alias"),
+            FindingKind::Cycle => write!(f, "cycle"),
+            FindingKind::TypeMismatch => write!(f, "type_mismatch"),
+            FindingKind::PermissionDenied => write!(f, "permission_denied"),
+            FindingKind::DanglingSymlink => write!(f, "dangling_symlink"),
+            FindingKind::DuplicateTarget => write!(f, "duplicate_target"),
+        }
+    }
+}
+
+// Data Type Implementations
+
+impl FindingKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            FindingKind::Unreachable
+            | FindingKind::UndefinedAlias
+            | FindingKind::Cycle
+            | FindingKind::TypeMismatch
+            | FindingKind::DanglingSymlink => Severity::Error,
+            FindingKind::PermissionDenied => Severity::Warning,
+            FindingKind::DuplicateTarget => Severity::Info,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            FindingKind::Unreachable        => "The shortpath does not exist on disk",
+            FindingKind::UndefinedAlias     => "The shortpath uses an alias that is not defined",
+            FindingKind::Cycle              => "The shortpath aliases depend on each other in a cycle",
+            FindingKind::TypeMismatch       => "A file is used as the base directory of other shortpaths",
+            FindingKind::PermissionDenied   => "The shortpath cannot be accessed",
+            FindingKind::DanglingSymlink    => "The shortpath is a symbolic link to a path that does not exist",
+            FindingKind::DuplicateTarget    => "Multiple shortpaths point to the same path",
+        }
+    }
+}
+
+impl CheckReport {
+    pub fn push(&mut self, name: &str, kind: FindingKind, message: String) {
+        let finding = Finding { name: name.to_owned(), kind, severity: kind.severity(), message };
+        debug!("Finding: {:?}", finding);
+        self.findings.push(finding);
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|finding| finding.severity == severity).count()
+    }
+
+    /// The most serious severity of any finding
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|finding| finding.severity).max()
+    }
+
+    /** Exit code for scripts
+      *
+      * 0 : No findings, or only informational findings
+      * 1 : At least one warning
+      * 2 : At least one error
+      */
+    pub fn exit_code(&self) -> i32 {
+        match self.max_severity() {
+            Some(Severity::Error) => 2,
+            Some(Severity::Warning) => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut output: String = self.findings.iter()
+            .map(|finding| format!("{}: {}\n", finding.severity, finding.message))
+            .collect();
+        output += &format!("Check Complete: {} errors, {} warnings\n", self.count(Severity::Error), self.count(Severity::Warning));
+        output
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not serialize check report.")
+    }
+
+    /// Format the report as SARIF 2.1.0 for code scanning tools
+    pub fn to_sarif(&self, config: Option<&Path>) -> String {
+        let mut kinds: Vec<FindingKind> = vec![];
+        self.findings.iter().for_each(|finding| if !kinds.contains(&finding.kind) { kinds.push(finding.kind) });
+
+        let rules: Vec<serde_json::Value> = kinds.iter().map(|kind| json!({
+            "id": kind.to_string(),
+            "shortDescription": { "text": kind.description() },
+            "defaultConfiguration": { "level": sarif_level(kind.severity()) },
+        })).collect();
+
+        let results: Vec<serde_json::Value> = self.findings.iter().map(|finding| {
+            let mut location = json!({ "logicalLocations": [{ "name": finding.name, "kind": "member" }] });
+            if let Some(config) = config {
+                location["physicalLocation"] = json!({ "artifactLocation": { "uri": file_uri(config) } });
+            }
+            json!({
+                "ruleId": finding.kind.to_string(),
+                "level": sarif_level(finding.severity),
+                "message": { "text": finding.message },
+                "locations": [location],
+            })
+        }).collect();
+
+        let sarif = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": { "driver": { "name": PROGRAM_NAME, "version": VERSION, "rules": rules } },
+                "results": results,
+            }],
+        });
+        serde_json::to_string_pretty(&sarif).expect("Could not serialize check report.")
+    }
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+// Checks
+
+/** Find every problem with the shortpaths
+  *
+  * Shortpaths with broken aliases are only reported for their aliases,
+  * and unreachable shortpaths that were sent to the trash are reported with their deletion date.
+  */
+pub fn check_shortpaths(shortpaths: &SP, cfg: &ResolveConfig) -> CheckReport {
+    info!("check_shortpaths()");
+    let mut report = CheckReport::default();
+    let trash = cfg.trash_dir().map(|dir| read_trash(&dir)).unwrap_or_default();
+
+    for (name, sp) in shortpaths.iter() {
+        let path = sp.path.display();
+        match alias_chain(name, shortpaths) {
+            Err(AliasError::Undefined(alias)) => {
+                report.push(name, FindingKind::UndefinedAlias, format!("{} shortpath uses undefined alias ${}: {}", name, alias, path));
+                continue;
+            }
+            Err(AliasError::Cycle(chain)) => {
+                let chain = chain.iter().map(|alias| format!("${}", alias)).collect::<Vec<String>>().join(" -> ");
+                report.push(name, FindingKind::Cycle, format!("{} shortpath has cyclic aliases: {} -> {}", name, name, chain));
+                continue;
+            }
+            Ok(_) => {}
+        }
+
+        let full_path = match &sp.full_path {
+            Some(full_path) => full_path,
+            None => {
+                report.push(name, FindingKind::Unreachable, format!("{} shortpath is unreachable: {}", name, path));
+                continue;
+            }
+        };
+
+        match full_path.metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                if let Err(e) = read_dir(full_path) {
+                    if e.kind() == ErrorKind::PermissionDenied {
+                        report.push(name, FindingKind::PermissionDenied, format!("{} shortpath cannot be read: {}", name, full_path.display()));
+                    }
+                }
+            }
+            Ok(_) => {
+                let dependents: Vec<&String> = shortpaths.iter()
+                    .filter(|(_, dependent)| get_alias_dependency(dependent).as_ref() == Some(name))
+                    .map(|(dependent, _)| dependent)
+                    .collect();
+                if !dependents.is_empty() {
+                    let dependents = dependents.iter().map(|d| d.as_str()).collect::<Vec<&str>>().join(", ");
+                    report.push(name, FindingKind::TypeMismatch, format!("{} shortpath is a file, but is used as a directory by: {}", name, dependents));
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                report.push(name, FindingKind::PermissionDenied, format!("{} shortpath cannot be accessed: {}", name, full_path.display()));
+            }
+            Err(_) if symlink_metadata(full_path).is_ok() => {
+                report.push(name, FindingKind::DanglingSymlink, format!("{} shortpath is a dangling symlink: {}", name, full_path.display()));
+            }
+            Err(_) => {
+                let message = match find_trashed(full_path, &trash) {
+                    Some(trashed) => format!("{} shortpath is unreachable: {} (trashed on {})", name, path, trashed.deleted_on()),
+                    None => format!("{} shortpath is unreachable: {}", name, path),
+                };
+                report.push(name, FindingKind::Unreachable, message);
+            }
+        }
+    }
+
+    // Report every shortpath after the first that points to the same path
+    let mut targets: IndexMap<PathBuf, &String> = IndexMap::new();
+    for (name, sp) in shortpaths.iter() {
+        let full_path = match &sp.full_path {
+            Some(full_path) => full_path.canonicalize().unwrap_or(full_path.to_owned()),
+            None => continue,
+        };
+        match targets.get(&full_path) {
+            Some(first) => report.push(name, FindingKind::DuplicateTarget, format!("{} shortpath has the same target as {}: {}", name, first, full_path.display())),
+            None => { targets.insert(full_path, name); }
+        }
+    }
+    report
+}
+
+/// Show the check report in the chosen format
+/// SARIF results are located in the shortpaths config file
+pub fn show_check_report(report: &CheckReport, format: ReportFormat, config: Option<&Path>) {
+    match format {
+        ReportFormat::Text => print!("{}", report.to_text()),
+        ReportFormat::Json => println!("{}", report.to_json()),
+        ReportFormat::Sarif => println!("{}", report.to_sarif(config)),
+    }
+}
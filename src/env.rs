@@ -14,7 +14,7 @@ pub struct EnvVars {
 /// Get the hashmap of every environment variable available
 pub fn env_vars() -> EP {
     let mut ep: EP = IndexMap::new();
-    vars().into_iter().for_each(|(var_name, var_path)| {
+    vars().for_each(|(var_name, var_path)| {
         ep.insert(var_name, var_path);
     });
     ep
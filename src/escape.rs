@@ -0,0 +1,130 @@
+use std::{
+    ffi::OsString,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+/** A piece of a path that is either valid UTF-8, or raw bytes that are not
+  *
+  * Export formats write the valid UTF-8 as is, and escape the raw bytes
+  * in whichever way the format supports.
+  */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathChunk<'a> {
+    Utf8(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// Split a path into its valid UTF-8 and invalid byte chunks
+pub fn path_chunks(path: &Path) -> Vec<PathChunk<'_>> {
+    let mut chunks = vec![];
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            chunks.push(PathChunk::Utf8(chunk.valid()));
+        }
+        if !chunk.invalid().is_empty() {
+            chunks.push(PathChunk::Bytes(chunk.invalid()));
+        }
+    }
+    chunks
+}
+
+/** Encode a path as a string, so it can be written to UTF-8 formats like TOML and JSON
+  *
+  * Bytes that are not valid UTF-8 are written as `\xHH`, and backslashes as `\\`.
+  * Every other character is written as is, so most paths are unchanged.
+  */
+pub fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for chunk in path_chunks(path) {
+        match chunk {
+            PathChunk::Utf8(s) => encoded += &s.replace('\\', "\\\\"),
+            PathChunk::Bytes(bytes) => bytes.iter().for_each(|byte| encoded += &format!("\\x{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decode a path that was encoded with `encode_path`
+/// Backslashes that don't start an escape sequence are kept as is
+pub fn decode_path(s: &str) -> PathBuf {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 2..i + 4).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], bytes.get(i + 1), hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'\\', Some(b'\\'), _)         => { decoded.push(b'\\'); i += 2; }
+            (b'\\', Some(b'x'), Some(byte)) => { decoded.push(byte); i += 4; }
+            (byte, _, _)                    => { decoded.push(byte); i += 1; }
+        }
+    }
+    PathBuf::from(OsString::from_vec(decoded))
+}
+
+/** Convert an absolute path to a `file://` URI
+  *
+  * Bytes are percent encoded like `g_filename_to_uri`,
+  * so paths that aren't valid UTF-8 can be bookmarked or reported.
+  */
+pub fn file_uri(path: &Path) -> String {
+    let encoded: String = path.as_os_str().as_bytes().iter().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9'
+        | b'-' | b'.' | b'_' | b'~' | b'/'
+        | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' => (*byte as char).to_string(),
+        byte => format!("%{:02X}", byte),
+    }).collect();
+    format!("file://{}", encoded)
+}
+
+/** Serialize paths to config files and resolve plans
+  * Valid UTF-8 paths are written as plain strings, exactly as configs written before
+  * paths were escaped, so their backslashes are never decoded.
+  * Other paths are escaped with `encode_path`, and written as a single element array: `["..."]`.
+  * A plain string in an older config can't be confused with the array.
+  *
+  * Use with `#[serde(with = "crate::escape::serde_path")]`.
+  */
+pub mod serde_path {
+    use super::{decode_path, encode_path};
+    use std::path::{Path, PathBuf};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum SerializedPath {
+        Utf8(String),
+        Escaped([String; 1]),
+    }
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        match path.to_str() {
+            Some(path) => SerializedPath::Utf8(path.to_owned()),
+            None => SerializedPath::Escaped([encode_path(path)]),
+        }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        match SerializedPath::deserialize(deserializer)? {
+            SerializedPath::Utf8(path) => Ok(PathBuf::from(path)),
+            SerializedPath::Escaped([escaped]) => Ok(decode_path(&escaped)),
+        }
+    }
+
+    /// Serialize optional paths, with `#[serde(with = "crate::escape::serde_path::option")]`
+    pub mod option {
+        use std::path::{Path, PathBuf};
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct SerializedPath(#[serde(with = "super")] PathBuf);
+
+        pub fn serialize<S: Serializer>(path: &Option<PathBuf>, serializer: S) -> Result<S::Ok, S::Error> {
+            path.as_deref().map(Path::to_path_buf).map(SerializedPath).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+            Ok(Option::<SerializedPath>::deserialize(deserializer)?.map(|SerializedPath(path)| path))
+        }
+    }
+}
@@ -1,36 +1,117 @@
 use crate::{
-    consts::PROGRAM_NAME,
-    export::{Export, ShellExporter},
+    consts::{PROGRAM_NAME, ORGANIZATION, APPLICATION, QUALIFIER},
+    escape::{PathChunk, path_chunks},
+    export::{Export, ShellExporter, alias_len, set_completions_fileperms},
+    shortpaths::SP,
 };
 
 use std::path::Path;
 
+use directories::ProjectDirs;
 use const_format::formatcp;
 
 // Constant Strings
 pub const BASH_DEFAULT: &str    = formatcp!("completions/{PROGRAM_NAME}.bash");
-pub const BASH_SYSTEM: &str     = formatcp!("/usr/share/bash-completion/completions/{PROGRAM_NAME}");
+pub const BASH_SYSTEM: &str     = formatcp!("/usr/share/{PROGRAM_NAME}/{PROGRAM_NAME}.bash");
 
-/* NOTE: Consider generating an actual bash completions
-   file instead of just generating the aliases script. */
+/// Jumps to shortpaths with `sp name/sub/dir`, and completes the names and their subdirectories
+pub const BASH_JUMP_FUNCTION: &str = r#"
+# Jump to a shortpath, or to a directory under it: sp proj/src
+sp() {
+    local name="${1%%/*}" rest=""
+    [[ "$1" == */* ]] && rest="${1#*/}"
+    if [[ ! " ${_SHORTPATH_ALIASES[*]} " =~ " $name " ]]; then
+        echo "sp: $name is not a shortpath" >&2
+        return 1
+    fi
+    cd -- "${!name}${rest:+/$rest}"
+}
+
+# Complete shortpath names, and then the directories under them
+_sp() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    COMPREPLY=()
+    compopt -o nospace 2>/dev/null
+    if [[ "$cur" != */* ]]; then
+        COMPREPLY=($(compgen -S / -W "${_SHORTPATH_ALIASES[*]}" -- "$cur"))
+        return
+    fi
+    local name="${cur%%/*}" rest="${cur#*/}" dir
+    [[ " ${_SHORTPATH_ALIASES[*]} " =~ " $name " ]] || return
+    local base="${!name}"
+    while IFS= read -r dir; do
+        COMPREPLY+=("$name/${dir#"$base/"}/")
+    done < <(compgen -d -- "$base/$rest")
+}
+complete -F _sp sp
+"#;
+
+/** Quote a path for bash and zsh
+  *
+  * Paths are double quoted so a leading alias like `$name` is still expanded.
+  * Every other `$`, `` ` ``, `"` and `\` is escaped, so sourcing the path never runs commands.
+  * Bytes that are not valid UTF-8 are written with ANSI-C quoting: `$'\xHH'`.
+  */
+pub fn quote_path(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        return String::from("\"\"");
+    }
+    let alias_len = alias_len(path);
+    let mut offset = 0;
+    path_chunks(path).into_iter().map(|chunk| match chunk {
+        PathChunk::Utf8(s) => {
+            let escaped: String = s.char_indices().map(|(i, c)| match c {
+                '$' if offset + i < alias_len => c.to_string(),
+                '$' | '`' | '"' | '\\' => format!("\\{}", c),
+                c => c.to_string(),
+            }).collect();
+            offset += s.len();
+            format!("\"{}\"", escaped)
+        }
+        PathChunk::Bytes(bytes) => {
+            offset += bytes.len();
+            let escaped: String = bytes.iter().map(|byte| format!("\\x{:02X}", byte)).collect();
+            format!("$'{}'", escaped)
+        }
+    }).collect()
+}
+
+/** Exports shortpaths as variables, along with the `sp` jump function and its completions
+  *
+  * Source the script in your `.bashrc`. It is not installed to the bash-completion directories,
+  * since bash-completion only loads those on demand, when completing `shortpath` itself.
+  */
 #[derive(Default)]
 pub struct BashExporter;
 impl ShellExporter for BashExporter {
     fn get_completions_sys_path(&self) -> String { BASH_SYSTEM.to_owned() }
     fn get_completions_user_path(&self) -> String {
-        let data_dir = dirs::data_dir();
-        format!("{}/bash-completion/completions/{}", data_dir.unwrap().to_str().unwrap(), PROGRAM_NAME)
+        match ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION) {
+            Some(proj_dirs) => format!("{}/completions/bash/{}.bash", proj_dirs.config_dir().display(), PROGRAM_NAME),
+            None => BASH_DEFAULT.to_owned(),
+        }
     }
-}
-
-impl Export for BashExporter {
-    fn get_completions_path(&self) -> String { BASH_DEFAULT.to_owned() }
 
     fn format_alias(&self, name: &str, path: &Path) -> String {
-        format!("export {}=\"{}\"\n", name, path.display())
+        format!("export {}={}\n", name, quote_path(path))
     }
 
     fn init_completions(&self) -> String {
         String::from("#!/bin/bash\n\n")
     }
+
+    fn gen_completions(&self, shortpaths: SP) -> String {
+        let names: Vec<String> = shortpaths.keys().filter(|name| self.is_valid_name(name)).cloned().collect();
+        let mut output = super::gen_shell_completions(self, shortpaths);
+        output += &format!("\n_SHORTPATH_ALIASES=({})\n", names.join(" "));
+        output += BASH_JUMP_FUNCTION;
+        output
+    }
+}
+
+impl Export for BashExporter {
+    fn get_export_path(&self) -> String { BASH_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> { Some(self.get_completions_user_path()) }
+    fn gen_export(&self, shortpaths: SP) -> String { self.gen_completions(shortpaths) }
+    fn set_completions_fileperms(&self, dest: &Path) { set_completions_fileperms(dest) }
 }
@@ -0,0 +1,79 @@
+use crate::{
+    export::{Export, ShellExporter, bash::BashExporter, is_identifier, read_existing, update_marked_block, write_export, MARKED_BLOCK_START, MARKED_BLOCK_END},
+    shortpaths::{SP, alias_chain, sort_by_dependencies, substitute_env_paths},
+};
+
+use std::{
+    env::current_dir,
+    io,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+
+// Constant Strings
+pub const DIRENV_DEFAULT: &str = ".envrc";
+
+/** The shortpaths inside a project directory, along with the aliases they depend on
+  *
+  * The aliases are ordered so every alias is set before the shortpaths that depend on it.
+  */
+pub fn project_shortpaths(shortpaths: &SP, project: &Path) -> SP {
+    let project = project.canonicalize().unwrap_or(project.to_path_buf());
+    let mut relevant = SP::new();
+    for (name, sp) in shortpaths.iter() {
+        let in_project = sp.full_path.as_ref().is_some_and(|full_path| full_path.starts_with(&project));
+        if !in_project {
+            continue;
+        }
+        let chain = alias_chain(name, shortpaths).unwrap_or_default();
+        for alias in std::iter::once(name).chain(chain.iter()) {
+            relevant.insert(alias.to_owned(), shortpaths[alias].to_owned());
+        }
+    }
+    debug!("Project Shortpaths: {:?}", relevant);
+    sort_by_dependencies(relevant)
+}
+
+/** Exports the shortpaths of a project to its direnv `.envrc`
+  *
+  * The shortpaths are written in a marked block, so the rest of the `.envrc` is left untouched.
+  * The project is the directory of the `.envrc`, or the current directory.
+  */
+#[derive(Default)]
+pub struct DirenvExporter {
+    pub project: Option<PathBuf>,
+}
+
+impl DirenvExporter {
+    /// Generate the marked block with the shortpaths of a project
+    pub fn gen_block(&self, shortpaths: &SP, project: &Path) -> String {
+        info!("gen_block()");
+        let shortpaths = substitute_env_paths(project_shortpaths(shortpaths, project));
+        let mut block = format!("{}\n", MARKED_BLOCK_START);
+        shortpaths.iter()
+            .filter(|(name, _)| is_identifier(name))
+            .for_each(|(name, sp)| block += &BashExporter.format_alias(name, &sp.path));
+        block += &format!("{}\n", MARKED_BLOCK_END);
+        block
+    }
+}
+
+impl Export for DirenvExporter {
+    fn get_export_path(&self) -> String { DIRENV_DEFAULT.to_owned() }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let project = self.project.to_owned().unwrap_or_else(|| current_dir().expect("Could not get current directory"));
+        self.gen_block(&shortpaths, &project)
+    }
+
+    fn write_export(&self, dest: &Path, shortpaths: SP) -> io::Result<PathBuf> {
+        let project = match dest.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => current_dir().expect("Could not get current directory"),
+        };
+        let envrc = read_existing(dest)?;
+        let output = update_marked_block(&envrc, &self.gen_block(&shortpaths, &project));
+        write_export(dest, &output)
+    }
+}
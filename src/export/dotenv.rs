@@ -0,0 +1,72 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    escape::encode_path,
+    export::{Export, ExportMetadata},
+    shortpaths::SP,
+};
+
+use std::path::Path;
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const DOTENV_DEFAULT: &str  = formatcp!("exports/{PROGRAM_NAME}.env");
+
+/** Quote a value for dotenv files
+  *
+  * Values are single quoted so they are never expanded.
+  * Values with single quotes or newlines are double quoted instead,
+  * with `\`, `"` and `$` escaped.
+  */
+pub fn quote_value(value: &str) -> String {
+    if !value.contains(['\'', '\n']) {
+        return format!("'{}'", value);
+    }
+    let escaped: String = value.chars().map(|c| match c {
+        '\\' | '"' | '$' => format!("\\{}", c),
+        '\n' => String::from("\\n"),
+        c => c.to_string(),
+    }).collect();
+    format!("\"{}\"", escaped)
+}
+
+/// Write a path on a single comment line
+fn comment_path(path: &Path) -> String {
+    encode_path(path).replace('\n', "\\n")
+}
+
+/** Exports shortpaths as a dotenv file
+  *
+  * Dotenv files can't reference other variables portably,
+  * so the expanded paths are exported, with the raw paths as comments.
+  */
+#[derive(Default)]
+pub struct DotenvExporter {
+    pub metadata: bool,
+}
+
+impl Export for DotenvExporter {
+    fn get_export_path(&self) -> String { DOTENV_DEFAULT.to_owned() }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let mut output = String::new();
+        if self.metadata {
+            let metadata = ExportMetadata::new();
+            output += &format!("# Generated by {} {} on {}\n\n", metadata.generator, metadata.version, metadata.generated_on);
+        }
+        for (name, sp) in shortpaths.iter() {
+            if !self.is_valid_name(name) {
+                eprintln!("Skipping {:?}: not a valid name for this export", name);
+                continue;
+            }
+            output += &format!("# {}: {}\n", name, comment_path(&sp.path));
+            // Dotenv files can't hold bytes that are not valid UTF-8
+            match sp.full_path.as_deref().map(|full_path| (full_path, full_path.to_str())) {
+                Some((_, Some(full_path))) => output += &format!("{}={}\n", name, quote_value(full_path)),
+                Some((full_path, None)) => output += &format!("# Skipped {}: {} is not valid UTF-8\n", name, comment_path(full_path)),
+                None => output += &format!("# Skipped {}: the path could not be expanded\n", name),
+            }
+        }
+        output
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    export::{Export, gen_expanded_export},
+    shortpaths::SP,
+};
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const ENVIRONMENT_D_DEFAULT: &str   = formatcp!("exports/50-{PROGRAM_NAME}.conf");
+
+/// Quote a value for environment.d, escaping `\`, `"`, `$` and `` ` ``
+pub fn quote_value(value: &str) -> String {
+    let escaped: String = value.chars().map(|c| match c {
+        '\\' | '"' | '$' | '`' => format!("\\{}", c),
+        c => c.to_string(),
+    }).collect();
+    format!("\"{}\"", escaped)
+}
+
+/** Exports shortpaths to systemd's environment.d, for user services and graphical sessions
+  *
+  * Values are fully expanded, since they can only reference variables set before them.
+  */
+#[derive(Default)]
+pub struct EnvironmentDExporter;
+
+impl Export for EnvironmentDExporter {
+    fn get_export_path(&self) -> String { ENVIRONMENT_D_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> {
+        let config_dir = dirs::config_dir();
+        Some(format!("{}/environment.d/50-{}.conf", config_dir.unwrap().display(), PROGRAM_NAME))
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        gen_expanded_export(self, &shortpaths, |name, value| {
+            // Values can't span multiple lines
+            Some(format!("{}={}\n", name, quote_value(value))).filter(|_| !value.contains('\n'))
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    escape::{PathChunk, path_chunks},
+    export::{Export, ShellExporter, alias_len, set_completions_fileperms},
+    shortpaths::SP,
+};
+
+use std::path::Path;
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const FISH_DEFAULT: &str    = formatcp!("completions/{PROGRAM_NAME}.fish");
+pub const FISH_SYSTEM: &str     = formatcp!("/usr/share/fish/vendor_conf.d/{PROGRAM_NAME}.fish");
+
+/** Quote a path for fish
+  *
+  * Paths are double quoted, and a leading `$name` reference is kept so fish expands it.
+  * Every other `$`, `"` and `\` is escaped.
+  * Bytes that are not valid UTF-8 are written unquoted as raw bytes: `\XHH`.
+  */
+pub fn quote_path(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        return String::from("\"\"");
+    }
+    let alias_len = alias_len(path);
+    let mut offset = 0;
+    path_chunks(path).into_iter().map(|chunk| match chunk {
+        PathChunk::Utf8(s) => {
+            let escaped: String = s.char_indices().map(|(i, c)| match c {
+                '$' if offset + i >= alias_len => String::from("\\$"),
+                '"' | '\\' => format!("\\{}", c),
+                c => c.to_string(),
+            }).collect();
+            offset += s.len();
+            format!("\"{}\"", escaped)
+        }
+        PathChunk::Bytes(bytes) => {
+            offset += bytes.len();
+            bytes.iter().map(|byte| format!("\\X{:02X}", byte)).collect()
+        }
+    }).collect()
+}
+
+#[derive(Default)]
+pub struct FishExporter;
+impl ShellExporter for FishExporter {
+    fn get_completions_sys_path(&self) -> String { FISH_SYSTEM.to_owned() }
+    fn get_completions_user_path(&self) -> String {
+        let config_dir = dirs::config_dir();
+        format!("{}/fish/conf.d/{}.fish", config_dir.unwrap().display(), PROGRAM_NAME)
+    }
+
+    fn format_alias(&self, name: &str, path: &Path) -> String {
+        format!("set -gx {} {}\n", name, quote_path(path))
+    }
+
+    fn init_completions(&self) -> String {
+        String::from("#!/usr/bin/env fish\n\n")
+    }
+}
+
+impl Export for FishExporter {
+    fn get_export_path(&self) -> String { FISH_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> { Some(self.get_completions_user_path()) }
+    fn gen_export(&self, shortpaths: SP) -> String { self.gen_completions(shortpaths) }
+    fn set_completions_fileperms(&self, dest: &Path) { set_completions_fileperms(dest) }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    escape::file_uri,
+    export::{Export, read_existing, write_export},
+    shortpaths::SP,
+};
+
+use std::{
+    fs::write,
+    io,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+
+// Constant Strings
+pub const GTK_BOOKMARKS_DEFAULT: &str = "exports/bookmarks";
+/// The extension of the file that records which bookmarks shortpath created
+pub const GTK_MANAGED_EXTENSION: &str = "shortpath";
+
+/// The bookmark lines for every shortpath with an absolute path
+/// Bookmark names can be anything but newlines
+pub fn bookmarks(shortpaths: &SP) -> Vec<String> {
+    shortpaths.iter().filter_map(|(name, sp)| {
+        let full_path = sp.full_path.as_ref().filter(|full_path| full_path.is_absolute())?;
+        Some(format!("{} {}", file_uri(full_path), name)).filter(|_| !name.contains('\n'))
+    }).collect()
+}
+
+/** Sync the shortpath bookmarks with the bookmarks file
+  *
+  * The bookmarks shortpath created before are replaced, and every other bookmark is kept as is.
+  * Each recorded bookmark owns a single line, starting from the end of the file where they were added,
+  * so a user's bookmark that is identical to one shortpath created is kept.
+  * Shortpaths that are already bookmarked by the user aren't added again.
+  * Returns the updated bookmarks file, and the bookmarks shortpath created.
+  */
+pub fn sync_bookmarks(existing: &str, managed: &[String], bookmarks: &[String]) -> (String, Vec<String>) {
+    let mut owned: Vec<&str> = managed.iter().map(String::as_str).collect();
+    let mut kept: Vec<&str> = existing.lines().rev().filter(|line| {
+        match owned.iter().position(|bookmark| bookmark == line) {
+            Some(i) => { owned.swap_remove(i); false }
+            None => true,
+        }
+    }).collect();
+    kept.reverse();
+    let uri = |line: &str| line.split(' ').next().unwrap_or_default().to_owned();
+    let user_uris: Vec<String> = kept.iter().map(|line| uri(line)).collect();
+    let added: Vec<String> = bookmarks.iter().filter(|bookmark| !user_uris.contains(&uri(bookmark))).cloned().collect();
+    debug!("Added Bookmarks: {:?}", added);
+
+    let output = kept.iter().map(|line| line.to_string()).chain(added.iter().cloned())
+        .map(|line| format!("{}\n", line))
+        .collect();
+    (output, added)
+}
+
+/** Exports shortpaths as GTK bookmarks, shown in the sidebars of Nautilus and other file managers
+  *
+  * The bookmarks shortpath created are recorded next to the bookmarks file,
+  * so they can be updated later without touching the user's own bookmarks.
+  */
+#[derive(Default)]
+pub struct GtkBookmarksExporter;
+
+impl Export for GtkBookmarksExporter {
+    fn get_export_path(&self) -> String { GTK_BOOKMARKS_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> {
+        let config_dir = dirs::config_dir();
+        Some(format!("{}/gtk-3.0/bookmarks", config_dir.unwrap().display()))
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        bookmarks(&shortpaths).iter().map(|bookmark| format!("{}\n", bookmark)).collect()
+    }
+
+    fn write_export(&self, dest: &Path, shortpaths: SP) -> io::Result<PathBuf> {
+        info!("write_export()");
+        let managed_file = dest.with_extension(GTK_MANAGED_EXTENSION);
+        let existing = read_existing(dest)?;
+        let managed: Vec<String> = read_existing(&managed_file)?.lines().map(String::from).collect();
+
+        let (output, added) = sync_bookmarks(&existing, &managed, &bookmarks(&shortpaths));
+        let managed: String = added.iter().map(|bookmark| format!("{}\n", bookmark)).collect();
+        write(&managed_file, managed)?;
+        write_export(dest, &output)
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    export::{Export, ExportDocument},
+    shortpaths::SP,
+};
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const JSON_DEFAULT: &str    = formatcp!("exports/{PROGRAM_NAME}.json");
+
+/** Exports shortpaths as JSON, with their raw and expanded paths */
+#[derive(Default)]
+pub struct JsonExporter {
+    pub metadata: bool,
+}
+
+impl Export for JsonExporter {
+    fn get_export_path(&self) -> String { JSON_DEFAULT.to_owned() }
+
+    fn is_valid_name(&self, name: &str) -> bool {
+        !name.is_empty()
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let document = ExportDocument::new(self, &shortpaths, self.metadata);
+        let mut output = serde_json::to_string_pretty(&document).expect("Could not serialize shortpaths to JSON.");
+        output.push('\n');
+        output
+    }
+}
@@ -1,117 +1,410 @@
 pub mod bash;
+pub mod direnv;
+pub mod dotenv;
+pub mod environment_d;
+pub mod fish;
+pub mod gtk;
+pub mod json;
+pub mod nushell;
+pub mod pam;
 pub mod powershell;
+pub mod user_dirs;
+pub mod yaml;
+pub mod zsh;
 
 use std::{
     path::{PathBuf, Path},
-    fs::{create_dir_all, write, set_permissions},
+    fs::{create_dir_all, read_to_string, write, set_permissions},
+    io::{self, ErrorKind},
     os::unix::prelude::PermissionsExt,
 };
 
 use crate::{
     app::ExportType,
-    export::{bash::BashExporter, powershell::PowershellExporter},
-    shortpaths::{SP, substitute_env_paths}
+    consts::{PROGRAM_NAME, VERSION},
+    escape::{encode_path, serde_path},
+    export::{
+        bash::BashExporter, direnv::DirenvExporter, dotenv::DotenvExporter, environment_d::EnvironmentDExporter, fish::FishExporter,
+        gtk::GtkBookmarksExporter, json::JsonExporter, nushell::NushellExporter, pam::PamExporter, powershell::PowershellExporter,
+        user_dirs::UserDirsExporter, yaml::YamlExporter, zsh::ZshExporter,
+    },
+    shortpaths::{SP, alias_chain, parse_alias, parse_env_alias, sort_by_dependencies, substitute_env_paths, to_string}
 };
 
+use chrono::Local;
+use indexmap::IndexMap;
 use log::{trace, info};
+use serde::Serialize;
+
+// Constant Strings
+/// Variables that are set for the session before systemd and PAM read the exported environment
+pub const SESSION_ENV_VARS: &[&str] = &["HOME", "USER", "LOGNAME", "SHELL"];
+/// The markers around the shortpaths of exports that update a file the user also edits
+pub const MARKED_BLOCK_START: &str  = "# >>> shortpath >>>";
+pub const MARKED_BLOCK_END: &str    = "# <<< shortpath <<<";
+
+// Data Types
+
+/// Options that change what the exporters generate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Export commands that jump to each shortpath
+    pub jump: bool,
+    /// Include metadata about the export in data formats
+    pub metadata: bool,
+    /// Match shortpath names to XDG user directories case-insensitively
+    pub ignore_case: bool,
+}
+
+/// Information about an export, for the applications that read it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportMetadata {
+    pub generator: String,
+    pub version: String,
+    pub generated_on: String,
+}
+
+/// A shortpath as written to data formats, with its raw and expanded paths
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportEntry {
+    #[serde(with = "serde_path")]
+    pub path: PathBuf,
+    #[serde(with = "serde_path::option")]
+    pub full_path: Option<PathBuf>,
+}
+
+/// The document written by the data format exporters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ExportMetadata>,
+    pub shortpaths: IndexMap<String, ExportEntry>,
+}
+
+// Data Type Implementations
+
+impl ExportMetadata {
+    pub fn new() -> ExportMetadata {
+        ExportMetadata {
+            generator: PROGRAM_NAME.to_owned(),
+            version: VERSION.to_owned(),
+            generated_on: Local::now().to_rfc3339(),
+        }
+    }
+}
+
+impl Default for ExportMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExportDocument {
+    /** Create the document for data formats
+      *
+      * Paths are written like in the config: valid UTF-8 paths as plain strings,
+      * and other paths as a single element array escaped with `encode_path`.
+      * Names that aren't valid for the exporter are skipped.
+      */
+    pub fn new<E: Export + ?Sized>(exp: &E, shortpaths: &SP, metadata: bool) -> ExportDocument {
+        let shortpaths = shortpaths.iter().filter(|(name, _)| {
+            let valid = exp.is_valid_name(name);
+            if !valid {
+                eprintln!("Skipping {:?}: not a valid name for this export", name);
+            }
+            valid
+        }).map(|(name, sp)| {
+            let entry = ExportEntry { path: sp.path.to_owned(), full_path: sp.full_path.to_owned() };
+            (name.to_owned(), entry)
+        }).collect();
+        let metadata = if metadata { Some(ExportMetadata::new()) } else { None };
+        ExportDocument { metadata, shortpaths }
+    }
+}
 
 // General purpose functions
+/** Check if a name is a valid shell variable identifier: `[A-Za-z_][A-Za-z0-9_]*` */
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 /** Make exported completions file rwx by the current only */
-fn set_completions_fileperms(dest: &Path) {
+pub(crate) fn set_completions_fileperms(dest: &Path) {
     let mut perms = dest.metadata().unwrap().permissions();
     perms.set_mode(0o744);
     set_permissions(dest, perms).unwrap_or_else(|_| panic!("Could not set permissions for {}", dest.display()));
 }
 
-/** Write shell completions to disk */
-fn write_completions(dest: &Path, output: &str) -> PathBuf {
-    write(dest, output).expect("Unable to write to disk");
-    set_completions_fileperms(dest);
-    dest.to_path_buf()
+/** Write an export to disk, keeping the permissions of an existing file */
+pub(crate) fn write_export(dest: &Path, output: &str) -> io::Result<PathBuf> {
+    write(dest, output).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", dest.display(), e)))?;
+    Ok(dest.to_path_buf())
+}
+
+/** Read a file an export updates, or an empty one if it doesn't exist yet
+  *
+  * Any other error is returned, so a file that can't be read is never overwritten.
+  */
+pub(crate) fn read_existing(path: &Path) -> io::Result<String> {
+    match read_to_string(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        contents => contents,
+    }
+}
+
+/** Replace the marked shortpath block of a file, or append it if there isn't one
+  *
+  * Everything outside of the block is kept as is.
+  */
+pub fn update_marked_block(existing: &str, block: &str) -> String {
+    let lines: Vec<&str> = existing.lines().collect();
+    let start = lines.iter().position(|line| line.trim_end() == MARKED_BLOCK_START);
+    let end = start.and_then(|start| lines.iter().skip(start).position(|line| line.trim_end() == MARKED_BLOCK_END).map(|end| start + end));
+
+    match (start, end) {
+        (Some(start), Some(end)) => {
+            let before: String = lines[..start].iter().map(|line| format!("{}\n", line)).collect();
+            let after: String = lines[end + 1..].iter().map(|line| format!("{}\n", line)).collect();
+            // Keep a missing trailing newline missing
+            let after = if existing.ends_with('\n') || after.is_empty() { after } else { after[..after.len() - 1].to_owned() };
+            format!("{}{}{}", before, block, after)
+        }
+        _ if existing.is_empty() => block.to_owned(),
+        _ if existing.ends_with('\n') => format!("{}\n{}", existing, block),
+        _ => format!("{}\n\n{}", existing, block),
+    }
 }
 
-fn gen_completions(shortpaths: SP, init_fn: impl Fn() -> String, transpile_fn: impl Fn(&str, &Path) -> String) -> String {
-    info!("gen_completions()");
-    let mut output = init_fn();
-    let shortpaths = substitute_env_paths(shortpaths);
+/** Generate the completions of every shortpath with an exporter
+  *
+  * Names that aren't valid for the exporter are skipped,
+  * since they would be written into the script as is.
+  */
+fn gen_shell_completions<E: ShellExporter + ?Sized>(exp: &E, shortpaths: SP) -> String {
+    info!("gen_shell_completions()");
+    let mut output = exp.init_completions();
+    let shortpaths = substitute_env_paths(sort_by_dependencies(shortpaths));
     shortpaths
         .iter().for_each(|(name, sp)| {
             trace!("shortpaths: {}: {}", &name, sp.path.display());
             trace!("shortpaths: {}: {:?}", &name, sp.full_path);
-            output += &transpile_fn(name, &sp.path);
+            if !exp.is_valid_name(name) {
+                eprintln!("Skipping {:?}: not a valid name for this export", name);
+                return;
+            }
+            output += &exp.format_alias(name, &sp.path);
     });
     trace!("output: {}", output);
     output
 }
 
-pub trait ShellExporter {
+/** The length of the leading `$name` alias reference of a path
+  *
+  * Exporters leave this reference unescaped so the shell expands it,
+  * and escape every other special character.
+  * Returns 0 if the path doesn't start with a reference to a valid identifier.
+  */
+pub fn alias_len(path: &Path) -> usize {
+    let comp = match path.components().next() {
+        Some(comp) => to_string(&comp),
+        None => return 0,
+    };
+    match parse_alias(comp.to_owned()) {
+        Some(alias) if is_identifier(&alias) => comp.len(),
+        _ => 0,
+    }
+}
+
+/// The environment variables a shortpath depends on, through its aliases
+pub fn env_dependencies(name: &str, shortpaths: &SP) -> Vec<String> {
+    let chain = alias_chain(name, shortpaths).unwrap_or_default();
+    std::iter::once(name.to_owned()).chain(chain).filter_map(|alias| {
+        let comp = shortpaths.get(&alias)?.path.components().next()?;
+        parse_env_alias(to_string(&comp))
+    }).collect()
+}
+
+/** Generate an export of fully expanded absolute paths, for formats that can't nest variables
+  *
+  * Shortpaths that can't be expanded to an absolute path, or written by `format_value`, are skipped.
+  * Values that depend on environment variables the format can't resolve
+  * are exported with their current value, with a warning.
+  */
+fn gen_expanded_export<E: Export + ?Sized>(exp: &E, shortpaths: &SP, format_value: impl Fn(&str, &str) -> Option<String>) -> String {
+    let mut output = String::new();
+    for (name, sp) in shortpaths.iter() {
+        if !exp.is_valid_name(name) {
+            eprintln!("Skipping {:?}: not a valid name for this export", name);
+            continue;
+        }
+        let value = match sp.full_path.as_deref() {
+            None => Err(String::from("the path could not be expanded")),
+            Some(full_path) if !full_path.is_absolute() => Err(format!("{} is not an absolute path", encode_path(full_path))),
+            Some(full_path) => full_path.to_str()
+                .ok_or_else(|| format!("{} is not valid UTF-8", encode_path(full_path)))
+                .and_then(|full_path| format_value(name, full_path).ok_or_else(|| format!("{} can't be written to this format", full_path))),
+        };
+        match value {
+            Ok(line) => {
+                env_dependencies(name, shortpaths).iter()
+                    .filter(|var| !SESSION_ENV_VARS.contains(&var.as_str()))
+                    .for_each(|var| {
+                        let warning = format!("{} depends on ${}, which can't be resolved here. Its current value was exported.", name, var);
+                        eprintln!("Warning: {}", warning);
+                        output += &format!("# Warning: {}\n", warning);
+                    });
+                output += &line;
+            }
+            Err(reason) => {
+                let reason = reason.replace('\n', "\\n");
+                eprintln!("Skipping {}: {}", name, reason);
+                output += &format!("# Skipped {}: {}\n", name, reason);
+            }
+        }
+    }
+    output
+}
+
+/** Export to shells, as a script that sets a variable for every shortpath */
+pub trait ShellExporter: Export {
     /** Get the user shell completions file path */
     fn get_completions_user_path(&self) -> String;
     /** Get the system shell completions file path */
     fn get_completions_sys_path(&self) -> String;
+
+    fn format_alias(&self, name: &str, path: &Path) -> String;
+
+    fn init_completions(&self) -> String {
+        String::new()
+    }
+
+    /** Generate shell completions */
+    fn gen_completions(&self, shortpaths: SP) -> String {
+        gen_shell_completions(self, shortpaths)
+    }
 }
 
 /** 
   * Export to multiple applications with a single unified interface
   *
-  * NOTE:
-  * The interface is very shell-related as of right now. When more formats
-  * are available, then this trait could be made more abstract.
+  * Shells implement the `ShellExporter` trait as well,
+  * and data formats serialize an `ExportDocument`.
   *
   * Export Paths:
   * Bash:
-  *     Default : ./completions/shortpaths.bash
-  *     System  :
-  *     User    : 
+  *     Default : ./completions/shortpath.bash
+  *     System  : /usr/share/shortpath/shortpath.bash
+  *     User    : ~/.config/shortpath/completions/bash/shortpath.bash
   *
   * Powershell
-  *     Default  : ./completions/shortpaths.ps1
-  *     System   : ./completions/shortpaths.ps1
-  *     User     :
+  *     Default  : ./completions/shortpath.ps1
+  *     System   : ~/.config/shortpath/completions/powershell/shortpath.ps1
+  *     User     : ~/.config/shortpath/completions/powershell/shortpath.ps1
+  *
+  * Fish
+  *     Default  : ./completions/shortpath.fish
+  *     System   : /usr/share/fish/vendor_conf.d/shortpath.fish
+  *     User     : ~/.config/fish/conf.d/shortpath.fish
+  *
+  * Nushell
+  *     Default  : ./completions/shortpath.nu
+  *     System   : ~/.config/nushell/scripts/shortpath.nu
+  *     User     : ~/.config/nushell/scripts/shortpath.nu
+  *
+  * Zsh
+  *     Default  : ./completions/shortpath.zsh
+  *     System   : /usr/share/zsh/site-functions/shortpath_aliases
+  *     User     : ~/.local/share/zsh/site-functions/shortpath_aliases
+  *
+  * Json, Yaml, Dotenv
+  *     Default  : ./exports/shortpath.{json,yaml,env}
+  *
+  * Systemd environment.d
+  *     Default  : ./exports/50-shortpath.conf
+  *     User     : ~/.config/environment.d/50-shortpath.conf
+  *
+  * PAM
+  *     Default  : ./exports/pam_environment
+  *     User     : ~/.pam_environment
+  *
+  * Direnv
+  *     Default  : ./.envrc
+  *
+  * GTK Bookmarks
+  *     Default  : ./exports/bookmarks
+  *     User     : ~/.config/gtk-3.0/bookmarks
+  *
+  * XDG User Directories
+  *     Default  : ./exports/user-dirs.dirs
+  *     User     : ~/.config/user-dirs.dirs
   */
 pub trait Export {
-    /** Get the default local platform independent shell completions path */
-    fn get_completions_path(&self) -> String;
+    /** Get the default local platform independent export path */
+    fn get_export_path(&self) -> String;
+
+    /** Get the path the export is installed to, so the user's applications load it */
+    fn get_install_path(&self) -> Option<String> {
+        None
+    }
 
     /** Ensure the directory exists at runtime */
     fn prepare_directory(&self, output_file: Option<PathBuf>) -> PathBuf {
         let dest = match output_file {
             Some(path)  => path,
-            None        => PathBuf::from(self.get_completions_path())
+            None        => PathBuf::from(self.get_export_path())
         };
 
         create_dir_all(dest.parent().expect("Could not get parent directory"))
-            .expect("Could not create shell completions directory");
+            .expect("Could not create export directory");
         dest
     }
 
-    fn set_completions_fileperms(&self, dest: &Path) {
-        set_completions_fileperms(dest)
-    }
-
-    fn format_alias(&self, name: &str, path: &Path) -> String;
+    /** Set the permissions of a newly created export. Only shell scripts change them */
+    fn set_completions_fileperms(&self, _dest: &Path) {}
 
-    fn init_completions(&self) -> String {
-        String::new()
+    /** Check if a shortpath name can be exported as is */
+    fn is_valid_name(&self, name: &str) -> bool {
+        is_identifier(name)
     }
 
-    /** Generate shell completions */
-    fn gen_completions(&self, shortpaths: SP) -> String {
-        let init_fn = || self.init_completions();
-        let transpile_fn = |name: &str, path: &Path| self.format_alias(name, path);
-        gen_completions(shortpaths, init_fn, transpile_fn)
-    }
+    /** Generate the export */
+    fn gen_export(&self, shortpaths: SP) -> String;
 
-    fn write_completions(&self, dest: &Path, shortpaths: SP) -> PathBuf {
-        let output = self.gen_completions(shortpaths);
-        write_completions(dest, &output)
+    /** Write the export to disk. Existing files keep their permissions */
+    fn write_export(&self, dest: &Path, shortpaths: SP) -> io::Result<PathBuf> {
+        let created = !dest.exists();
+        let output = self.gen_export(shortpaths);
+        write_export(dest, &output)?;
+        if created {
+            self.set_completions_fileperms(dest);
+        }
+        Ok(dest.to_path_buf())
     }
 }
 
 /** Returns the specific exporter */
 pub fn get_exporter(export_type: ExportType) -> Box<dyn Export> {
+    get_exporter_with(export_type, ExportOptions::default())
+}
+
+/** Returns the specific exporter, configured with the export options */
+pub fn get_exporter_with(export_type: ExportType, options: ExportOptions) -> Box<dyn Export> {
     match export_type {
         ExportType::Bash => Box::<BashExporter>::default(),
         ExportType::Powershell => Box::<PowershellExporter>::default(),
+        ExportType::Fish => Box::<FishExporter>::default(),
+        ExportType::Nushell => Box::new(NushellExporter { jump_commands: options.jump }),
+        ExportType::Zsh => Box::<ZshExporter>::default(),
+        ExportType::Json => Box::new(JsonExporter { metadata: options.metadata }),
+        ExportType::Yaml => Box::new(YamlExporter { metadata: options.metadata }),
+        ExportType::Dotenv => Box::new(DotenvExporter { metadata: options.metadata }),
+        ExportType::EnvironmentD => Box::<EnvironmentDExporter>::default(),
+        ExportType::Pam => Box::<PamExporter>::default(),
+        ExportType::Direnv => Box::<DirenvExporter>::default(),
+        ExportType::GtkBookmarks => Box::<GtkBookmarksExporter>::default(),
+        ExportType::UserDirs => Box::new(UserDirsExporter { ignore_case: options.ignore_case }),
     }
 }
@@ -0,0 +1,102 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    escape::encode_path,
+    export::{Export, ShellExporter, alias_len, set_completions_fileperms},
+    shortpaths::SP,
+};
+
+use std::path::Path;
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const NUSHELL_DEFAULT: &str = formatcp!("completions/{PROGRAM_NAME}.nu");
+
+/// Nushell commands that jump commands must not shadow once the module is used
+pub const NUSHELL_BUILTINS: &[&str] = &[
+    "alias", "all", "any", "append", "ast", "cal", "cd", "char", "clear", "collect", "columns", "compact",
+    "complete", "config", "const", "cp", "date", "debug", "decode", "def", "default", "describe", "detect",
+    "do", "drop", "du", "each", "echo", "encode", "enumerate", "error", "every", "exec", "exit", "explain",
+    "explore", "export", "extern", "fill", "filter", "find", "first", "flatten", "for", "format", "from",
+    "get", "glob", "grid", "group", "headers", "help", "hide", "histogram", "history", "http", "if",
+    "ignore", "input", "insert", "inspect", "into", "is-empty", "items", "join", "keybindings", "kill",
+    "last", "length", "let", "lines", "load-env", "loop", "ls", "match", "math", "merge", "metadata",
+    "mkdir", "mktemp", "module", "move", "mut", "mv", "nu-check", "nu-highlight", "open", "overlay",
+    "panic", "par-each", "parse", "path", "plugin", "prepend", "print", "ps", "random", "range", "reduce",
+    "reject", "rename", "return", "reverse", "rm", "run-external", "save", "schema", "scope", "select",
+    "seq", "shuffle", "skip", "sleep", "slice", "sort", "sort-by", "source", "source-env", "split", "start",
+    "str", "sys", "table", "take", "tee", "term", "timeit", "to", "touch", "transpose", "try", "tutor",
+    "ulimit", "uname", "uniq", "update", "upsert", "url", "use", "values", "version", "view", "watch",
+    "where", "which", "while", "whoami", "window", "with-env", "wrap", "zip",
+];
+
+/** Quote a path for nushell
+  *
+  * A leading `$name` reference is written as `$env.name` in an interpolated string.
+  * Paths without a reference are written as plain double quoted strings.
+  */
+pub fn quote_path(path: &str) -> String {
+    let escape = |s: &str, chars: &[char]| -> String {
+        s.chars().map(|c| if chars.contains(&c) { format!("\\{}", c) } else { c.to_string() }).collect()
+    };
+    match alias_len(Path::new(path)) {
+        0 => format!("\"{}\"", escape(path, &['\\', '"'])),
+        len => {
+            let (alias, rest) = path.split_at(len);
+            format!("$\"($env.{}){}\"", &alias[1..], escape(rest, &['\\', '"', '(']))
+        }
+    }
+}
+
+/** Exports shortpaths as a nushell module
+  *
+  * The environment variables are set in an `export-env` block when the module is used.
+  * Jump commands that `cd` into each shortpath can be exported too.
+  */
+#[derive(Default)]
+pub struct NushellExporter {
+    pub jump_commands: bool,
+}
+
+impl ShellExporter for NushellExporter {
+    fn get_completions_sys_path(&self) -> String { self.get_completions_user_path() }
+    fn get_completions_user_path(&self) -> String {
+        let config_dir = dirs::config_dir();
+        format!("{}/nushell/scripts/{}.nu", config_dir.unwrap().display(), PROGRAM_NAME)
+    }
+
+    fn format_alias(&self, name: &str, path: &Path) -> String {
+        // Nushell strings can't hold bytes that are not valid UTF-8
+        match path.to_str() {
+            Some(path) => format!("    $env.{} = {}\n", name, quote_path(path)),
+            None => format!("    # Skipped {}: {} is not valid UTF-8\n", name, encode_path(path)),
+        }
+    }
+
+    fn init_completions(&self) -> String {
+        String::from("export-env {\n")
+    }
+
+    fn gen_completions(&self, shortpaths: SP) -> String {
+        let names: Vec<String> = shortpaths.keys().filter(|name| self.is_valid_name(name)).cloned().collect();
+        let mut output = super::gen_shell_completions(self, shortpaths);
+        output += "}\n";
+        if self.jump_commands {
+            names.iter().for_each(|name| {
+                if NUSHELL_BUILTINS.contains(&name.as_str()) {
+                    output += &format!("\n# Skipped jump command for {}: it would shadow the nushell command {}\n", name, name);
+                } else {
+                    output += &format!("\nexport def --env {} [] {{ cd $env.{} }}\n", name, name);
+                }
+            });
+        }
+        output
+    }
+}
+
+impl Export for NushellExporter {
+    fn get_export_path(&self) -> String { NUSHELL_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> { Some(self.get_completions_user_path()) }
+    fn gen_export(&self, shortpaths: SP) -> String { self.gen_completions(shortpaths) }
+    fn set_completions_fileperms(&self, dest: &Path) { set_completions_fileperms(dest) }
+}
@@ -0,0 +1,56 @@
+use crate::{
+    export::{Export, gen_expanded_export, read_existing, update_marked_block, write_export, MARKED_BLOCK_START, MARKED_BLOCK_END},
+    shortpaths::SP,
+};
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+// Constant Strings
+pub const PAM_DEFAULT: &str = "exports/pam_environment";
+
+/** Quote a value for pam_env
+  *
+  * `\`, `$` and `@` are escaped so they aren't expanded.
+  * Returns `None` for values with double quotes or newlines, which pam_env can't read.
+  */
+pub fn quote_value(value: &str) -> Option<String> {
+    if value.contains(['"', '\n']) {
+        return None;
+    }
+    let escaped: String = value.chars().map(|c| match c {
+        '\\' | '$' | '@' => format!("\\{}", c),
+        c => c.to_string(),
+    }).collect();
+    Some(format!("\"{}\"", escaped))
+}
+
+/** Exports shortpaths to `~/.pam_environment`, for every login session
+  *
+  * Values are fully expanded, since pam_env only knows a few session variables.
+  * The shortpaths are written in a marked block, so the rest of the file is left untouched.
+  */
+#[derive(Default)]
+pub struct PamExporter;
+
+impl Export for PamExporter {
+    fn get_export_path(&self) -> String { PAM_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> {
+        let home_dir = dirs::home_dir();
+        Some(format!("{}/.pam_environment", home_dir.unwrap().display()))
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let variables = gen_expanded_export(self, &shortpaths, |name, value| {
+            quote_value(value).map(|value| format!("{} DEFAULT={}\n", name, value))
+        });
+        format!("{}\n{}{}\n", MARKED_BLOCK_START, variables, MARKED_BLOCK_END)
+    }
+
+    fn write_export(&self, dest: &Path, shortpaths: SP) -> io::Result<PathBuf> {
+        let existing = read_existing(dest)?;
+        write_export(dest, &update_marked_block(&existing, &self.gen_export(shortpaths)))
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     consts::{PROGRAM_NAME, ORGANIZATION, APPLICATION, QUALIFIER},
-    export::{Export, ShellExporter},
+    escape::encode_path,
+    export::{Export, ShellExporter, alias_len, set_completions_fileperms},
+    shortpaths::SP,
 };
 
 use std::path::Path;
@@ -11,6 +13,43 @@ use const_format::formatcp;
 // Constant Strings
 pub const POWERSHELL_DEFAULT: &str  = formatcp!("completions/{PROGRAM_NAME}.ps1");
 
+/// Wraps `Move-Item` and `Remove-Item` to call `shortpath hook`
+pub const POWERSHELL_HOOKS: &str    = include_str!("../../hooks/shortpaths_hooks.ps1");
+
+/// Completes shortpath names for the `shortpath` command, showing their paths as tooltips
+pub const POWERSHELL_COMPLETER: &str = r#"
+Register-ArgumentCompleter -Native -CommandName shortpath -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $ShortpathAliases | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+        $path = (Get-Item -Path "Env:$_" -ErrorAction SilentlyContinue).Value
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', "$path")
+    }
+}
+"#;
+
+/** Quote a path for PowerShell
+  *
+  * A leading `$name` alias is written as `${Env:name}` so it refers to the exported variable.
+  * Every other `` ` ``, `$` and double quote (including the typographic ones PowerShell also accepts)
+  * is escaped with a backtick, so the path is never evaluated.
+  */
+pub fn quote_path(path: &str) -> String {
+    let (alias, rest) = path.split_at(alias_len(Path::new(path)));
+    let escaped: String = rest.chars().map(|c| match c {
+        '`' | '$' | '"' | '\u{201C}' | '\u{201D}' | '\u{201E}' => format!("`{}", c),
+        c => c.to_string(),
+    }).collect();
+    match alias.strip_prefix('$') {
+        Some(alias) => format!("\"${{Env:{}}}{}\"", alias, escaped),
+        None => format!("\"{}\"", escaped),
+    }
+}
+
+/** Exports shortpaths as a PowerShell profile script
+  *
+  * The script sets the `$Env:` variables, wraps `Move-Item` and `Remove-Item`
+  * with the shortpath hooks, and registers a completer for the shortpath names.
+  */
 #[derive(Default)]
 pub struct PowershellExporter;
 
@@ -19,14 +58,33 @@ impl ShellExporter for PowershellExporter {
     fn get_completions_user_path(&self) -> String {
         let proj_dirs = ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION).unwrap();
         let data_dir = proj_dirs.config_dir();
-        format!("{}/completions/powershell/{}.ps1", data_dir.to_path_buf().to_str().unwrap(), PROGRAM_NAME)
+        format!("{}/completions/powershell/{}.ps1", data_dir.display(), PROGRAM_NAME)
     }
-}
-
-impl Export for PowershellExporter {
-    fn get_completions_path(&self) -> String { POWERSHELL_DEFAULT.to_owned() }
 
     fn format_alias(&self, name: &str, path: &Path) -> String {
-        format!("$Env:{} = \"{}\"\n", name, path.display())
+        // PowerShell strings can't hold bytes that are not valid UTF-8
+        match path.to_str() {
+            Some(path) => format!("$Env:{} = {}\n", name, quote_path(path)),
+            None => format!("# Skipped {}: {} is not valid UTF-8\n", name, encode_path(path)),
+        }
+    }
+
+    fn gen_completions(&self, shortpaths: SP) -> String {
+        let names: Vec<String> = shortpaths.iter()
+            .filter(|(name, sp)| self.is_valid_name(name) && sp.path.to_str().is_some())
+            .map(|(name, _)| format!("'{}'", name))
+            .collect();
+        let mut output = super::gen_shell_completions(self, shortpaths);
+        output += &format!("\n{}", POWERSHELL_HOOKS);
+        output += &format!("\n$ShortpathAliases = @({})\n", names.join(", "));
+        output += POWERSHELL_COMPLETER;
+        output
     }
 }
+
+impl Export for PowershellExporter {
+    fn get_export_path(&self) -> String { POWERSHELL_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> { Some(self.get_completions_user_path()) }
+    fn gen_export(&self, shortpaths: SP) -> String { self.gen_completions(shortpaths) }
+    fn set_completions_fileperms(&self, dest: &Path) { set_completions_fileperms(dest) }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    export::{Export, read_existing, write_export},
+    shortpaths::SP,
+};
+
+use std::{
+    fs::write,
+    io,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+
+// Constant Strings
+pub const USER_DIRS_DEFAULT: &str = "exports/user-dirs.dirs";
+/// The extension of the file that records the user directories before shortpath changed them
+pub const USER_DIRS_BACKUP_EXTENSION: &str = "shortpath";
+
+/// The XDG user directories, and the shortpath names that set them
+pub const USER_DIRS: &[(&str, &str)] = &[
+    ("desktop",     "XDG_DESKTOP_DIR"),
+    ("download",    "XDG_DOWNLOAD_DIR"),
+    ("downloads",   "XDG_DOWNLOAD_DIR"),
+    ("templates",   "XDG_TEMPLATES_DIR"),
+    ("publicshare", "XDG_PUBLICSHARE_DIR"),
+    ("public",      "XDG_PUBLICSHARE_DIR"),
+    ("documents",   "XDG_DOCUMENTS_DIR"),
+    ("music",       "XDG_MUSIC_DIR"),
+    ("pictures",    "XDG_PICTURES_DIR"),
+    ("videos",      "XDG_VIDEOS_DIR"),
+];
+
+/// Get the XDG user directory a shortpath name sets
+/// Names like `Music` only match when `ignore_case` is set
+pub fn user_dir_key(name: &str, ignore_case: bool) -> Option<&'static str> {
+    USER_DIRS.iter()
+        .find(|(dir, _)| *dir == name || (ignore_case && dir.eq_ignore_ascii_case(name)))
+        .map(|(_, key)| *key)
+}
+
+/// Get the key of a `user-dirs.dirs` line
+fn line_key(line: &str) -> &str {
+    line.split('=').next().unwrap_or_default().trim()
+}
+
+/** Format a user directory value
+  *
+  * Paths in the home directory are written relative to `$HOME`, like `xdg-user-dirs` does.
+  * Returns `None` for paths that aren't absolute or valid UTF-8.
+  */
+pub fn user_dir_value(path: &Path, home: &Path) -> Option<String> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let value = match path.strip_prefix(home) {
+        Ok(rest) if rest.as_os_str().is_empty() => String::from("$HOME"),
+        Ok(rest) => format!("$HOME/{}", escape(rest.to_str()?)),
+        Err(_) => escape(path.to_str()?),
+    };
+    Some(format!("\"{}\"", value))
+}
+
+/// Escape the characters that are special in double quotes, since the file is sourced by shells
+fn escape(value: &str) -> String {
+    value.chars().map(|c| match c {
+        '\\' | '"' | '$' | '`' => format!("\\{}", c),
+        c => c.to_string(),
+    }).collect()
+}
+
+/// The user directory entries set by the shortpaths
+/// The first shortpath for a user directory is used
+pub fn user_dir_entries(shortpaths: &SP, home: &Path, ignore_case: bool) -> IndexMap<&'static str, String> {
+    let mut entries = IndexMap::new();
+    for (name, sp) in shortpaths.iter() {
+        let (key, full_path) = match (user_dir_key(name, ignore_case), &sp.full_path) {
+            (Some(key), Some(full_path)) => (key, full_path),
+            _ => continue,
+        };
+        match user_dir_value(full_path, home) {
+            Some(value) => { entries.entry(key).or_insert(format!("{}={}", key, value)); }
+            None => eprintln!("Skipping {}: {} is not an absolute UTF-8 path", name, full_path.display()),
+        }
+    }
+    entries
+}
+
+/** Replace the user directory entries set by the shortpaths
+  *
+  * Every other line is kept as is, and new entries are appended.
+  */
+pub fn update_user_dirs(existing: &str, entries: &IndexMap<&'static str, String>) -> String {
+    let mut written: Vec<&str> = vec![];
+    let mut output = String::new();
+    for line in existing.lines() {
+        let key = line_key(line);
+        match entries.get(key) {
+            Some(entry) if !written.contains(&key) => {
+                output += &format!("{}\n", entry);
+                written.push(key);
+            }
+            Some(_) => {}
+            None => output += &format!("{}\n", line),
+        }
+    }
+    entries.iter()
+        .filter(|(key, _)| !written.contains(key))
+        .for_each(|(_, entry)| output += &format!("{}\n", entry));
+    output
+}
+
+/** Record the user directory entries an update replaces
+  *
+  * Only the first value of each user directory is recorded, so it can always be restored.
+  * Returns the updated record.
+  */
+pub fn record_user_dirs(record: &str, existing: &str, entries: &IndexMap<&'static str, String>) -> String {
+    let mut record = record.to_owned();
+    for line in existing.lines() {
+        let key = line_key(line);
+        let replaced = entries.get(key).is_some_and(|entry| entry != line);
+        if replaced && !record.lines().any(|recorded| line_key(recorded) == key) {
+            record += &format!("{}\n", line);
+        }
+    }
+    record
+}
+
+/** Exports shortpaths named after XDG user directories to `user-dirs.dirs`
+  *
+  * A shortpath named `documents` sets `XDG_DOCUMENTS_DIR`, for example.
+  * The replaced entries are recorded next to `user-dirs.dirs`, and every change is printed.
+  */
+#[derive(Default)]
+pub struct UserDirsExporter {
+    /// Also match names like `Music` or `DOCUMENTS`
+    pub ignore_case: bool,
+}
+
+impl Export for UserDirsExporter {
+    fn get_export_path(&self) -> String { USER_DIRS_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> {
+        let config_dir = dirs::config_dir();
+        Some(format!("{}/user-dirs.dirs", config_dir.unwrap().display()))
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let home = dirs::home_dir().unwrap_or_default();
+        update_user_dirs("", &user_dir_entries(&shortpaths, &home, self.ignore_case))
+    }
+
+    fn write_export(&self, dest: &Path, shortpaths: SP) -> io::Result<PathBuf> {
+        let home = dirs::home_dir().unwrap_or_default();
+        let existing = read_existing(dest)?;
+        let entries = user_dir_entries(&shortpaths, &home, self.ignore_case);
+
+        let record_file = dest.with_extension(USER_DIRS_BACKUP_EXTENSION);
+        let record = read_existing(&record_file)?;
+        write(&record_file, record_user_dirs(&record, &existing, &entries))?;
+        entries.values()
+            .filter(|entry| !existing.lines().any(|line| &line == entry))
+            .for_each(|entry| println!("Set {}", entry));
+        write_export(dest, &update_user_dirs(&existing, &entries))
+    }
+}
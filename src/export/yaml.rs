@@ -0,0 +1,29 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    export::{Export, ExportDocument},
+    shortpaths::SP,
+};
+
+use const_format::formatcp;
+
+// Constant Strings
+pub const YAML_DEFAULT: &str    = formatcp!("exports/{PROGRAM_NAME}.yaml");
+
+/** Exports shortpaths as YAML, with their raw and expanded paths */
+#[derive(Default)]
+pub struct YamlExporter {
+    pub metadata: bool,
+}
+
+impl Export for YamlExporter {
+    fn get_export_path(&self) -> String { YAML_DEFAULT.to_owned() }
+
+    fn is_valid_name(&self, name: &str) -> bool {
+        !name.is_empty()
+    }
+
+    fn gen_export(&self, shortpaths: SP) -> String {
+        let document = ExportDocument::new(self, &shortpaths, self.metadata);
+        serde_yaml::to_string(&document).expect("Could not serialize shortpaths to YAML.")
+    }
+}
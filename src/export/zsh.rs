@@ -0,0 +1,49 @@
+use crate::{
+    consts::PROGRAM_NAME,
+    export::{Export, ShellExporter, bash::quote_path, set_completions_fileperms},
+    shortpaths::SP,
+};
+
+use std::path::Path;
+
+use const_format::formatcp;
+
+// Constant Strings
+/// The function installed to `$fpath`, which sets the shortpaths when it is called
+pub const ZSH_FUNCTION: &str    = formatcp!("{PROGRAM_NAME}_aliases");
+pub const ZSH_DEFAULT: &str     = formatcp!("completions/{PROGRAM_NAME}.zsh");
+pub const ZSH_SYSTEM: &str      = formatcp!("/usr/share/zsh/site-functions/{ZSH_FUNCTION}");
+
+/** Exports shortpaths as variables and zsh named directories
+  *
+  * Every alias is also registered with `hash -d`, so `~name` can be used
+  * in prompts, completions and `cd`. The script is installed to a `site-functions`
+  * directory in `$fpath` as the `shortpath_aliases` function, which `export` and `hash -d`
+  * set globally. It can also be sourced as is.
+  */
+#[derive(Default)]
+pub struct ZshExporter;
+impl ShellExporter for ZshExporter {
+    fn get_completions_sys_path(&self) -> String { ZSH_SYSTEM.to_owned() }
+    fn get_completions_user_path(&self) -> String {
+        match dirs::data_dir() {
+            Some(data_dir) => format!("{}/zsh/site-functions/{}", data_dir.display(), ZSH_FUNCTION),
+            None => ZSH_DEFAULT.to_owned(),
+        }
+    }
+
+    fn format_alias(&self, name: &str, path: &Path) -> String {
+        format!("export {}={}\nhash -d {}=\"${}\"\n", name, quote_path(path), name, name)
+    }
+
+    fn init_completions(&self) -> String {
+        String::from("#!/bin/zsh\n\n")
+    }
+}
+
+impl Export for ZshExporter {
+    fn get_export_path(&self) -> String { ZSH_DEFAULT.to_owned() }
+    fn get_install_path(&self) -> Option<String> { Some(self.get_completions_user_path()) }
+    fn gen_export(&self, shortpaths: SP) -> String { self.gen_completions(shortpaths) }
+    fn set_completions_fileperms(&self, dest: &Path) { set_completions_fileperms(dest) }
+}
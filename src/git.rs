@@ -0,0 +1,200 @@
+use crate::{
+    helpers::{Scope, SearchIndex},
+    resolve::ResolveConfig,
+    shortpaths::{Shortpath, SP},
+};
+
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use indexmap::IndexMap;
+use log::{debug, info, trace};
+use serde::{Serialize, Deserialize};
+
+// Data Types
+
+/// The git repositories of shortpaths that target a repository root
+pub type Repos = IndexMap<String, GitRepo>;
+
+/** The identity of a git repository
+  *
+  * Repositories are recorded in the `[repos]` table of the shortpaths config,
+  * so they can be found again by `resolve` after being moved or re-cloned.
+  */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitRepo {
+    pub remote: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_commit: Option<String>,
+}
+
+// Parsing
+
+/// Parse the url of a remote from a `.git/config` file
+/// The `origin` remote is preferred, otherwise the first remote is used
+pub fn parse_remote_url(config: &str) -> Option<String> {
+    let mut remotes: Vec<(String, String)> = vec![];
+    let mut section: Option<String> = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line.strip_prefix("[remote \"")
+                .and_then(|remote| remote.strip_suffix("\"]"))
+                .map(str::to_owned);
+            continue;
+        }
+        if let (Some(remote), Some((key, url))) = (&section, line.split_once('=')) {
+            if key.trim() == "url" {
+                remotes.push((remote.to_owned(), url.trim().to_owned()));
+            }
+        }
+    }
+    let origin = remotes.iter().position(|(remote, _)| remote == "origin").unwrap_or(0);
+    remotes.into_iter().nth(origin).map(|(_, url)| url)
+}
+
+/** Normalize a remote url so the different ways of writing it compare equal
+  *
+  * `git@github.com:user/repo.git`, `ssh://git@github.com/user/repo` and
+  * `https://github.com/user/repo/` all become `github.com/user/repo`.
+  * User names and credentials are dropped, so normalized remotes are safe to store,
+  * and normalizing a normalized remote doesn't change it.
+  */
+pub fn normalize_remote(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let url = match url.split_once("://") {
+        Some((_, rest)) => rest.to_owned(),
+        None => url.replacen(':', "/", 1), // scp-like syntax
+    };
+    let (host, path) = url.split_once('/').unwrap_or((&url, ""));
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host).replacen(':', "/", 1);
+    match path {
+        "" => host.to_lowercase(),
+        path => format!("{}/{}", host, path).to_lowercase(),
+    }
+}
+
+/// Get the root commit of a repository, if git is available
+pub fn root_commit(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["rev-list", "--max-parents=0", "HEAD"])
+        .output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    stdout.lines().next().map(str::to_owned)
+}
+
+/** Find the config file of a repository root directory
+  *
+  * `.git` is a directory in regular repositories, and a file pointing to the git directory
+  * in worktrees and submodules. Worktrees share the config of their main repository.
+  */
+pub fn git_config_path(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    let git_dir = match dot_git.is_dir() {
+        true => dot_git,
+        false => {
+            let conts = read_to_string(&dot_git).ok()?;
+            let git_dir = conts.lines().find_map(|line| line.strip_prefix("gitdir:"))?.trim();
+            dir.join(git_dir)
+        }
+    };
+    let common_dir = match read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir,
+    };
+    Some(common_dir.join("config"))
+}
+
+/// Read the git repository of a repository root directory
+pub fn read_git_repo(dir: &Path) -> Option<GitRepo> {
+    let config = read_to_string(git_config_path(dir)?).ok()?;
+    let remote = parse_remote_url(&config)?;
+    Some(GitRepo { remote, root_commit: root_commit(dir) })
+}
+
+// Recording
+
+/// Record the git repositories of every reachable shortpath that targets a repository root
+/// Repositories of unreachable shortpaths are kept so they can be resolved later
+/// Only run when shortpaths are added, updated or resolved, since every repository config is read
+pub fn update_repos(shortpaths: &SP, repos: &mut Repos) {
+    info!("update_repos()");
+    repos.retain(|name, _| shortpaths.contains_key(name));
+    for (name, sp) in shortpaths.iter() {
+        let full_path = match &sp.full_path {
+            Some(full_path) if full_path.exists() => full_path,
+            _ => continue,
+        };
+        let config = match git_config_path(full_path).and_then(|config| read_to_string(config).ok()) {
+            Some(config) => config,
+            None => { repos.remove(name); continue; }
+        };
+        let remote = match parse_remote_url(&config) {
+            Some(remote) => normalize_remote(&remote),
+            None => { repos.remove(name); continue; }
+        };
+        // Avoid running git again for repositories we already know
+        let known = repos.get(name).is_some_and(|repo| repo.remote == remote && repo.root_commit.is_some());
+        if !known {
+            debug!("Recording repository {}: {}", name, remote);
+            repos.insert(name.to_owned(), GitRepo { remote, root_commit: root_commit(full_path) });
+        }
+    }
+}
+
+// Searching
+
+/// Search the parent directories for sibling repositories, and recursively in every search root
+/// Shortpaths that couldn't be expanded are not searched for
+pub fn in_repo_scopes(sp: &Shortpath, cfg: &ResolveConfig) -> Vec<Scope> {
+    let full_path = match &sp.full_path {
+        Some(full_path) => full_path,
+        None => return vec![],
+    };
+    let mut scopes: Vec<Scope> = full_path.ancestors().skip(1)
+        .filter(|dir| !cfg.is_excluded(dir) && (cfg.roots.is_empty() || cfg.in_roots(dir)))
+        .map(|dir| (dir.to_path_buf(), Some(2)))
+        .collect();
+    scopes.extend(cfg.roots().into_iter().map(|root| (root, cfg.max_depth.map(|depth| depth + 1))));
+    scopes
+}
+
+/** Find the repository roots that match a recorded git repository
+  *
+  * Repositories with a matching remote score 0.9, and 1.0 if their root commit also matches.
+  * The best matches are returned first.
+  */
+pub fn find_repos(repo: &GitRepo, scopes: &[Scope], index: &SearchIndex) -> Vec<(PathBuf, f64)> {
+    let remote = normalize_remote(&repo.remote);
+    let mut found: Vec<(PathBuf, f64)> = vec![];
+    let git_dirs = scopes.iter()
+        .flat_map(|scope| index[scope].iter())
+        .filter(|file| file.file_name() == ".git");
+
+    for git_dir in git_dirs {
+        let dir = git_dir.path().parent().unwrap().to_path_buf();
+        if found.iter().any(|(found, _)| found == &dir) {
+            continue;
+        }
+        let config = git_config_path(&dir).and_then(|config| read_to_string(config).ok()).unwrap_or_default();
+        let matches = parse_remote_url(&config).is_some_and(|url| normalize_remote(&url) == remote);
+        if !matches {
+            continue;
+        }
+        trace!("\tFound: {}", dir.display());
+        let score = match &repo.root_commit {
+            Some(commit) if root_commit(&dir).as_ref() == Some(commit) => 1.0,
+            _ => 0.9,
+        };
+        found.push((dir, score));
+    }
+    found.sort_by(|(_, s1), (_, s2)| s2.total_cmp(s1));
+    found
+}
@@ -1,19 +1,30 @@
-use crate::shortpaths::{Shortpath, SP};
+use crate::{
+    resolve::{Candidates, ResolveAction, ResolveCandidate, ResolveConfig, ResolveStrategy},
+    shortpaths::{Shortpath, SP},
+    trash::read_trash_info,
+};
 use std::{
-    env::var,
+    env::{current_dir, var},
+    ffi::OsString,
     path::{Path, PathBuf},
     io::{stdin, stdout, Write},
 };
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use log::{debug, trace, info};
+use rayon::prelude::*;
 use walkdir::{DirEntry, WalkDir};
 
 // Types
 pub type SearchResults = Vec<DirEntry>;
 pub type ScopeResults = Vec<(PathBuf, SearchResults)>;
-pub type SearchFn = fn(&Shortpath, WalkDir) -> SearchResults;
-pub type ScopeFn = fn(&Shortpath, SearchFn) -> ScopeResults;
+pub type Walk = Box<dyn Iterator<Item = DirEntry>>;
+/// A directory to search, and how many directories deep to search it
+pub type Scope = (PathBuf, Option<usize>);
+/// The wanted files found in each scope, shared by all unreachable shortpaths
+pub type SearchIndex = IndexMap<Scope, SearchResults>;
+pub type SearchFn = fn(&Shortpath, &[DirEntry]) -> SearchResults;
+pub type ScopeFn = fn(&Shortpath, &ResolveConfig) -> Vec<Scope>;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum ResolveChoices {
@@ -58,26 +69,38 @@ pub fn expand_tilde<P: AsRef<Path>>(path_user_input: P) -> Option<PathBuf> {
     })
 }
 
+/// Expands ~/ and makes relative paths absolute to the current directory
+pub fn absolute_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = expand_tilde(path).expect("Could not find home directory");
+    match path.is_absolute() {
+        true => path,
+        false => current_dir().expect("Could not get current directory").join(path),
+    }
+}
+
 /// Get user input
+/// Returns `None` once the input has ended, or can't be read
 pub fn prompt(message: &str) -> Option<String> {
     info!("prompt()");
     print!("{}", message);
     stdout().lock().flush().expect("Unable to write prompt to STDOUT");
 
     let mut input = String::new();
-    stdin().read_line(&mut input).expect("Failed to get user input");
+    stdin().read_line(&mut input).ok()?;
     debug!("Input Received: {}", &input.trim_end());
 
     if !input.is_empty() { Some(input) } else { None }
 }
 
 /// Repeatedly prompt user until a valid input is given
-pub fn prompt_until_valid(message: &str, is_valid: impl Fn(String) -> bool) -> String {
-    let mut input = prompt(message);
-    while !is_valid(input.clone().unwrap()) {
-        input = prompt(message);
+/// Returns `None` if the input ends before that
+pub fn prompt_until_valid(message: &str, is_valid: impl Fn(String) -> bool) -> Option<String> {
+    loop {
+        let input = prompt(message)?;
+        if is_valid(input.clone()) {
+            return Some(input);
+        }
     }
-    input.unwrap()
 }
 
 /// Tab align right strings
@@ -110,15 +133,105 @@ pub fn sort_shortpaths(shortpaths: SP) -> SP {
 
 // Searching
 
-/// Find files for unreachable shortpaths
-pub fn search_for(search_fn: SearchFn, scope_fn: ScopeFn, unreachable: &SP) -> IndexMap<String, ScopeResults> {
+/** Find files for unreachable shortpaths
+  *
+  * The scopes of every unreachable shortpath are walked only once, in parallel,
+  * and then each shortpath is matched against the shared search index.
+  * Only the files named like an unreachable shortpath are indexed.
+  */
+pub fn search_for(search_fn: SearchFn, scope_fn: ScopeFn, unreachable: &SP, cfg: &ResolveConfig) -> IndexMap<String, ScopeResults> {
     info!("search_for()");
-    unreachable.iter().map(|(name, sp)| {
-        let found = scope_fn(sp, search_fn);
+    let scopes: IndexMap<&String, Vec<Scope>> = unreachable.iter()
+        .map(|(name, sp)| (name, scope_fn(sp, cfg)))
+        .collect();
+
+    let unique: IndexSet<Scope> = scopes.values().flatten().cloned().collect();
+    let wanted: IndexSet<OsString> = unreachable.values()
+        .filter_map(|sp| sp.path.file_name().map(|name| name.to_owned()))
+        .collect();
+    let index = index_scopes(unique, &wanted, cfg);
+
+    scopes.into_iter().map(|(name, scopes)| {
+        let sp = &unreachable[name];
+        let mut found: ScopeResults = vec![];
+        for scope in scopes {
+            let matches: SearchResults = search_fn(sp, &index[&scope]).into_iter()
+                .filter(|file| !found.iter().any(|(_, files)| files.iter().any(|f| f.path() == file.path())))
+                .collect();
+            matches.iter().for_each(|f| trace!("\tFound: {}", f.path().display()));
+            found.push((scope.0, matches));
+        }
         (name.to_owned(), found)
     }).collect()
 }
 
+/// Check if every directory searched by the inner scope is also searched by the outer scope
+pub fn covers_scope(outer: &Scope, inner: &Scope) -> bool {
+    let ((outer_dir, outer_depth), (dir, depth)) = (outer, inner);
+    let nested = match dir.strip_prefix(outer_dir) {
+        Ok(rest) => rest.components().count(),
+        Err(_) => return false,
+    };
+    match (outer_depth, depth) {
+        (None, _) => true,
+        (Some(outer_depth), Some(depth)) => nested + depth <= *outer_depth,
+        (Some(_), None) => false,
+    }
+}
+
+/// Remove the scopes that are searched by other scopes, so no directory is walked twice
+pub fn merge_scopes(scopes: &IndexSet<Scope>) -> IndexSet<Scope> {
+    scopes.iter()
+        .filter(|scope| !scopes.iter().any(|outer| outer != *scope && covers_scope(outer, scope)))
+        .cloned()
+        .collect()
+}
+
+/** Walk every scope in parallel, and index the wanted files found in each
+  *
+  * Nested scopes are merged before walking, and only files with a wanted name are kept,
+  * so the index stays small even for large search roots.
+  */
+pub fn index_scopes(scopes: IndexSet<Scope>, wanted: &IndexSet<OsString>, cfg: &ResolveConfig) -> SearchIndex {
+    info!("index_scopes()");
+    let merged: Vec<Scope> = merge_scopes(&scopes).into_iter().collect();
+    let walked: Vec<(Scope, SearchResults)> = merged.into_par_iter()
+        .map(|(dir, max_depth)| {
+            debug!("Searching Directory {}", dir.display());
+            let files = walk_dir(&dir, max_depth, cfg)
+                .filter(|file| wanted.contains(file.file_name()))
+                .collect();
+            ((dir, max_depth), files)
+        }).collect();
+
+    // Every scope gets the files of the walked scope it is in, within its own depth
+    scopes.into_iter().map(|scope| {
+        let (walked_scope, files) = walked.iter().find(|(walked, _)| covers_scope(walked, &scope)).unwrap();
+        let (dir, max_depth) = &scope;
+        let files = if *walked_scope == scope { files.to_owned() } else {
+            files.iter().filter(|file| match file.path().strip_prefix(dir) {
+                Ok(rest) => max_depth.is_none_or(|depth| rest.components().count() <= depth),
+                Err(_) => false,
+            }).cloned().collect()
+        };
+        (scope, files)
+    }).collect()
+}
+
+/// Walk a directory within the bounds of the resolve config
+/// Excluded directories are skipped entirely, along with any unreadable files
+pub fn walk_dir(dir: &Path, max_depth: Option<usize>, cfg: &ResolveConfig) -> Walk {
+    let mut walker = WalkDir::new(dir).follow_links(cfg.follow_symlinks);
+    if let Some(max_depth) = max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+    let cfg = cfg.to_owned();
+    let walk = walker.into_iter()
+        .filter_entry(move |entry| !cfg.is_excluded(entry.path()))
+        .filter_map(Result::ok);
+    Box::new(walk)
+}
+
 
 // Scope Functions
 
@@ -126,46 +239,45 @@ pub fn search_for(search_fn: SearchFn, scope_fn: ScopeFn, unreachable: &SP) -> I
 /// Returns the first set of matching results
 /// NOTE: This may be adjusted later to return more than just the first set of matching results
 /// if it is fast and efficient enough, for use in more complex functions
-pub fn in_parent_dir(sp: &Shortpath, search_fn: SearchFn) -> ScopeResults {
-    let full_path = sp.full_path.clone().unwrap();
-    let mut next = full_path.parent();
-    
-    let mut found = vec![];
-    while let Some(dir) = next {
-        debug!("Searching Directory {}", dir.display());
-        let parent_files = WalkDir::new(dir).max_depth(1);
-        let matches = search_fn(sp, parent_files);
-        matches.iter().for_each(|f| trace!("\tFound: {}", &f.file_name().to_str().unwrap()));
-        found.push((dir.to_path_buf(), matches));
-        next = dir.parent(); // Continue searching
-    }
-    found
+/// If search roots are given, only the parent directories inside the roots are searched
+/// Shortpaths that couldn't be expanded have no parent directories to search
+pub fn in_parent_dir(sp: &Shortpath, cfg: &ResolveConfig) -> Vec<Scope> {
+    let full_path = match &sp.full_path {
+        Some(full_path) => full_path,
+        None => return vec![],
+    };
+    full_path.ancestors().skip(1)
+        .filter(|dir| !cfg.is_excluded(dir) && (cfg.roots.is_empty() || cfg.in_roots(dir)))
+        .map(|dir| (dir.to_path_buf(), Some(1)))
+        .collect()
+}
+
+/// Search for files in parent directories, and then recursively in every search root
+/// The roots are searched no deeper than the max_depth of the resolve config
+pub fn in_search_roots(sp: &Shortpath, cfg: &ResolveConfig) -> Vec<Scope> {
+    let mut scopes = in_parent_dir(sp, cfg);
+    scopes.extend(cfg.roots().into_iter().map(|root| (root, cfg.max_depth)));
+    scopes
 }
 
 // Search Functions
-pub fn matching_file_names(sp: &Shortpath, dir: WalkDir) -> Vec<DirEntry> {
-    let file_name = sp.path.file_name().unwrap().to_str().unwrap();
-    dir.into_iter()
-        .filter_map(Result::ok)
+pub fn matching_file_names(sp: &Shortpath, files: &[DirEntry]) -> Vec<DirEntry> {
+    let file_name = sp.path.file_name().unwrap();
+    files.iter()
         .filter(|file| file.file_name() == file_name)
+        .cloned()
         .collect()
 }
 
-// Resolve Mode
-
-/// Automatically chooses the best candidate to resolve the shortpath to
-pub fn auto_resolve(name: String, results: ScopeResults) -> Option<(String, PathBuf)> {
-    let results: Vec<Vec<DirEntry>> = results.into_iter()
-        .filter(|(_, nested_entries)| !nested_entries.to_owned().is_empty())
-        .into_iter()
-        .map(|(_,nested_entries)| nested_entries)
-        .collect();
-
-    if !results.is_empty() {
-        let path = results.first().unwrap().first().unwrap().to_owned();
-        return Some((name, path.path().to_path_buf()))
-    }
-    None
+/// Score a candidate by how near it is to the unreachable path
+/// Candidates found in the same parent directory score 1.0, and less for each ancestor above
+pub fn score_candidate(previous: &Path, candidate: &Path) -> f64 {
+    let common = previous.components()
+        .zip(candidate.components())
+        .take_while(|(c1, c2)| c1 == c2)
+        .count();
+    let distance = previous.components().count().saturating_sub(common).max(1);
+    1.0 / distance as f64
 }
 
 // Manual Resolve
@@ -180,30 +292,97 @@ pub fn get_choice(input: String) -> Option<ResolveChoices> {
     }
 }
 
-/// Manually prompt user to resolve unreachable Shortpath
-pub fn manual_resolve(name: String, previous: &Path, results: ScopeResults) -> Option<(String, PathBuf)> {
-    let mut choice: Option<ResolveChoices> = None;
+/** Manually prompt user to choose one of the candidates for an unreachable Shortpath
+  *
+  * Returns the choice, along with the chosen candidate when one was overwritten.
+  * `skip_all` and `overwrite_all` are returned right away, so the caller can apply them to the remaining shortpaths.
+  * Returns `None` if the input ended.
+  */
+pub fn manual_resolve_candidates(name: &str, previous: &Path, candidates: &[PathBuf]) -> Option<(ResolveChoices, Option<PathBuf>)> {
+    let is_valid_input = |input: String| get_choice(input).is_some();
+    for file in candidates {
+        let message = format!("Update {} from {} to {}? [overwrite, overwrite_all, skip, skip_all]: ",
+    name, &previous.display(), &file.display());
+        let input = prompt_until_valid(&message, is_valid_input)?;
+
+        match get_choice(input)? {
+            ResolveChoices::Skip    => continue,
+            ResolveChoices::SkipAll => return Some((ResolveChoices::SkipAll, None)),
+            choice                  => return Some((choice, Some(file.to_path_buf()))),
+        }
+    }
+    Some((ResolveChoices::Skip, None))
+}
+
+/// Manually prompt user to restore or remove a trashed Shortpath
+/// Returns `Some(None)` if the shortpath is skipped, and `None` if the input ended
+pub fn manual_resolve_trashed(name: &str, candidates: &[ResolveCandidate]) -> Option<Option<ResolveCandidate>> {
     let is_valid_input = |input: String| {
-        matches!(input.to_lowercase().trim_end(), "overwrite" | "overwrite_all" | "skip" | "skip_all")
+        matches!(input.to_lowercase().trim_end(), "restore" | "remove" | "skip")
     };
-    for (_, search_results) in results.iter() {
-        for file in search_results {
-
-            if (choice.is_some()) && (choice.as_ref() == Some(&ResolveChoices::OverwriteAll)) {
-                return Some((name, file.path().to_path_buf()))
-            }
+    let original = &candidates.first()?.full_path;
+    let trashed = candidates.iter().find_map(|candidate| match &candidate.action {
+        ResolveAction::Restore { trashed, info } => Some((trashed, read_trash_info(info.parent()?.parent()?, info))),
+        _ => None,
+    });
+    let message = match trashed {
+        Some((trashed, entry)) => {
+            let deleted_on = entry.map(|entry| entry.deleted_on()).unwrap_or_else(|| String::from("an unknown date"));
+            format!("{} was trashed on {}. Restore {} from {}? [restore, remove, skip]: ",
+                name, deleted_on, original.display(), trashed.display())
+        }
+        None => format!("{} was trashed. Restore {} from the trash? [restore, remove, skip]: ", name, original.display()),
+    };
+    let input = prompt_until_valid(&message, is_valid_input)?;
 
-            let message = format!("Update {} from {} to {}? [overwrite, overwrite_all, skip, skip_all]: ",
-    name, &previous.display(), &file.path().display());
-            let input = prompt_until_valid(&message, is_valid_input);
-            choice = get_choice(input);
+    let chosen = |action: &str| candidates.iter().find(|candidate| candidate.action.to_string() == action).cloned();
+    match input.to_lowercase().trim_end() {
+        "restore"   => Some(chosen("restore")),
+        "remove"    => Some(chosen("remove")),
+        _           => Some(None),
+    }
+}
 
-            match choice.unwrap() {
-                ResolveChoices::Skip    => continue,
-                ResolveChoices::SkipAll => continue,
-                _                       => return Some((name, file.path().to_path_buf())),
+/** Prompt the user line by line to choose a candidate for each unreachable Shortpath
+  *
+  * After `overwrite_all`, the best candidate of every remaining shortpath is chosen,
+  * and after `skip_all` the remaining shortpaths are skipped. Trashed shortpaths are still asked about after `overwrite_all`.
+  * Prompting stops when the input ends, keeping the choices made so far.
+  */
+pub fn prompt_candidates(unreachable: &SP, candidates: &Candidates) -> IndexMap<String, ResolveCandidate> {
+    let mut chosen = IndexMap::new();
+    let mut overwrite_all = false;
+    for (name, ranked) in candidates.iter() {
+        let best = match ranked.first() {
+            Some(best) => best,
+            None => continue,
+        };
+        if best.strategy == ResolveStrategy::Trash {
+            match manual_resolve_trashed(name, ranked) {
+                Some(Some(candidate)) => { chosen.insert(name.to_owned(), candidate); }
+                Some(None) => {}
+                None => break,
             }
+            continue;
+        }
+        if overwrite_all {
+            chosen.insert(name.to_owned(), best.to_owned());
+            continue;
+        }
+        let previous = unreachable[name].full_path.to_owned().unwrap();
+        let paths: Vec<PathBuf> = ranked.iter().map(|candidate| candidate.full_path.to_owned()).collect();
+        let (choice, path) = match manual_resolve_candidates(name, &previous, &paths) {
+            Some(answer) => answer,
+            None => break,
+        };
+        if let Some(candidate) = path.and_then(|path| ranked.iter().find(|candidate| candidate.full_path == path)) {
+            chosen.insert(name.to_owned(), candidate.to_owned());
+        }
+        match choice {
+            ResolveChoices::OverwriteAll => overwrite_all = true,
+            ResolveChoices::SkipAll => break,
+            _ => {}
         }
     }
-    None
+    chosen
 }
@@ -2,9 +2,17 @@
 //#![feature(type_alias_impl_trait)]
 pub mod app;
 pub mod builder;
+pub mod check;
 pub mod config;
 pub mod consts;
 pub mod env;
+pub mod escape;
 pub mod export;
+pub mod git;
 pub mod helpers;
+pub mod lint;
+pub mod picker;
+pub mod refactor;
+pub mod resolve;
 pub mod shortpaths;
+pub mod trash;
@@ -0,0 +1,223 @@
+use crate::{
+    app::ExportType,
+    export::get_exporter,
+    shortpaths::{SP, Shortpath, find_fold, get_alias_dependency, parse_alias, to_string},
+};
+
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use log::{debug, info};
+
+// Constant Strings
+/// Environment variables that break the shell or other programs when overwritten
+pub const WELL_KNOWN_ENV_VARS: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "PWD", "OLDPWD", "CDPATH", "IFS",
+    "PS1", "PS2", "PS4", "TERM", "LANG", "EDITOR", "VISUAL", "PAGER", "TMPDIR", "TZ",
+    "DISPLAY", "MANPATH", "INFOPATH", "PYTHONPATH", "LD_LIBRARY_PATH", "LD_PRELOAD",
+];
+
+// Data Types
+
+/// The kinds of problems `lint` can find
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// The name can't be exported to an export target
+    InvalidName(ExportType),
+    ShadowsEnvVar,
+    /// The path could be folded into another alias
+    UnfoldedPrefix(String),
+    TrailingSlash,
+    ParentDir,
+    RelativePath,
+}
+
+/// How a lint can be fixed automatically
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintFix {
+    Rename(String),
+    Path(PathBuf),
+}
+
+/// A single problem with a shortpath name or path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The shortpath alias name
+    pub name: String,
+    pub kind: LintKind,
+    pub fix: Option<LintFix>,
+}
+
+// Trait Implementations
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = &self.name;
+        match &self.kind {
+            LintKind::InvalidName(export_type) => {
+                let target = export_type.to_possible_value().unwrap();
+                write!(f, "{} is not a valid name for {} exports", name, target.get_name())?
+            }
+            LintKind::ShadowsEnvVar     => write!(f, "{} shadows the ${} environment variable", name, name)?,
+            LintKind::UnfoldedPrefix(alias) => write!(f, "{} could use the ${} alias", name, alias)?,
+            LintKind::TrailingSlash     => write!(f, "{} has a trailing slash", name)?,
+            LintKind::ParentDir         => write!(f, "{} has a '..' segment", name)?,
+            LintKind::RelativePath      => write!(f, "{} is a relative path", name)?,
+        }
+        match &self.fix {
+            Some(LintFix::Rename(fixed)) => write!(f, " (fix: rename to {})", fixed),
+            Some(LintFix::Path(fixed)) => write!(f, " (fix: {})", fixed.display()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Data Type Implementations
+
+impl Lint {
+    /// Names that would break exports or the user's shell
+    pub fn is_dangerous(&self) -> bool {
+        matches!(self.kind, LintKind::InvalidName(_) | LintKind::ShadowsEnvVar)
+    }
+}
+
+// Names
+
+/// Replace every character that isn't valid in an identifier with an underscore
+pub fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Find the problems with a shortpath name
+pub fn lint_name(name: &str) -> Vec<Lint> {
+    let mut lints: Vec<Lint> = ExportType::value_variants().iter()
+        .filter(|export_type| !get_exporter(**export_type).is_valid_name(name))
+        .map(|export_type| {
+            let fix = Some(LintFix::Rename(sanitize_name(name))).filter(|_| !name.is_empty());
+            Lint { name: name.to_owned(), kind: LintKind::InvalidName(*export_type), fix }
+        }).collect();
+    if WELL_KNOWN_ENV_VARS.contains(&name) || name.starts_with("XDG_") {
+        lints.push(Lint { name: name.to_owned(), kind: LintKind::ShadowsEnvVar, fix: None });
+    }
+    lints
+}
+
+// Paths
+
+/** Remove `..` segments that follow a literal directory
+  *
+  * Segments that follow an alias or another `..` are kept,
+  * since they can't be removed without expanding the path.
+  */
+pub fn normalize_parent_dirs(path: &Path) -> PathBuf {
+    let mut normalized: Vec<Component> = vec![];
+    for comp in path.components() {
+        let is_literal = |comp: &Component| matches!(comp, Component::Normal(_)) && parse_alias(to_string(comp)).is_none();
+        match (comp, normalized.last()) {
+            (Component::ParentDir, Some(last)) if is_literal(last) => { normalized.pop(); }
+            (Component::CurDir, _) => {}
+            _ => normalized.push(comp),
+        }
+    }
+    normalized.iter().collect()
+}
+
+/// Find the problems with a shortpath path
+pub fn lint_path(name: &str, sp: &Shortpath, shortpaths: &SP) -> Vec<Lint> {
+    let path = sp.path.to_string_lossy();
+    let is_literal = get_alias_dependency(sp).is_none() && !path.starts_with('$');
+    let mut kinds: Vec<LintKind> = vec![];
+    let mut fixed = sp.path.clone();
+
+    if path.len() > 1 && path.ends_with('/') {
+        kinds.push(LintKind::TrailingSlash);
+        fixed = sp.path.components().collect();
+    }
+    if sp.path.components().any(|comp| comp == Component::ParentDir) {
+        kinds.push(LintKind::ParentDir);
+        fixed = normalize_parent_dirs(&fixed);
+    }
+    if is_literal && sp.path.is_relative() && !path.starts_with('~') {
+        kinds.push(LintKind::RelativePath);
+    }
+    if is_literal && sp.path.is_absolute() {
+        if let Some((alias, rest)) = find_fold(&fixed, shortpaths, None, Some(name)) {
+            kinds.push(LintKind::UnfoldedPrefix(alias.name().to_owned()));
+            fixed = PathBuf::from(alias.to_prefix()).join(rest);
+        }
+    }
+
+    let fix = Some(LintFix::Path(fixed)).filter(|fix| *fix != LintFix::Path(sp.path.clone()));
+    kinds.into_iter().map(|kind| {
+        let fix = if kind == LintKind::RelativePath { None } else { fix.clone() };
+        Lint { name: name.to_owned(), kind, fix }
+    }).collect()
+}
+
+/// Find the problems with every shortpath name and path
+pub fn lint_shortpaths(shortpaths: &SP) -> Vec<Lint> {
+    info!("lint_shortpaths()");
+    let lints: Vec<Lint> = shortpaths.iter().flat_map(|(name, sp)| {
+        let mut lints = lint_name(name);
+        lints.extend(lint_path(name, sp, shortpaths));
+        lints
+    }).collect();
+    debug!("Lints: {:?}", lints);
+    lints
+}
+
+/** Apply the fixes of the lints
+  *
+  * Paths are fixed before names, and renamed aliases are also renamed in the paths
+  * that depend on them. Names that are already taken are not renamed.
+  * Returns the lints that were fixed.
+  */
+pub fn fix_lints(shortpaths: &mut SP, lints: &[Lint]) -> Vec<Lint> {
+    info!("fix_lints()");
+    let mut fixed = vec![];
+    for lint in lints {
+        if let Some(LintFix::Path(path)) = &lint.fix {
+            if let Some(sp) = shortpaths.get_mut(&lint.name) {
+                sp.path = path.to_owned();
+                fixed.push(lint.to_owned());
+            }
+        }
+    }
+    for lint in lints {
+        if let Some(LintFix::Rename(new_name)) = &lint.fix {
+            if !shortpaths.contains_key(&lint.name) {
+                // Already renamed for another export target
+                fixed.push(lint.to_owned());
+                continue;
+            }
+            if shortpaths.contains_key(new_name) {
+                eprintln!("Could not rename {} to {}: {} already exists", lint.name, new_name, new_name);
+                continue;
+            }
+            rename_alias(shortpaths, &lint.name, new_name);
+            fixed.push(lint.to_owned());
+        }
+    }
+    fixed
+}
+
+/// Rename a shortpath in place, along with the paths that depend on it
+pub fn rename_alias(shortpaths: &mut SP, name: &str, new_name: &str) {
+    *shortpaths = shortpaths.drain(..).map(|(key, mut sp)| {
+        if get_alias_dependency(&sp).as_deref() == Some(name) {
+            let rest: PathBuf = sp.path.components().skip(1).collect();
+            sp.path = PathBuf::from(format!("${}", new_name)).join(rest);
+        }
+        let key = if key == name { new_name.to_owned() } else { key };
+        (key, sp)
+    }).collect();
+}
@@ -1,21 +1,45 @@
 use shortpath::app::{create_logger, CLI, Commands, Hooks};
+use shortpath::check::{check_shortpaths, show_check_report};
 use shortpath::builder::{ShortpathsBuilder, ShortpathOperationsExt, to_disk};
 use shortpath::consts::CONFIG_FILE_PATH;
+use shortpath::git::update_repos;
+use shortpath::export::{ExportOptions, get_exporter};
+use shortpath::helpers::{absolute_path, prompt_until_valid};
+use shortpath::refactor::{plan_refactor, apply_refactor};
+use shortpath::lint::{Lint, lint_name, lint_shortpaths, fix_lints};
+use shortpath::resolve::{ResolveAction, ResolveConfig, ResolvePlan};
 use shortpath::shortpaths::{
     add_shortpath,
     remove_shortpath,
-    check_shortpaths,
-    resolve,
+    plan_resolve,
+    apply_resolve_plan,
+    show_resolve_plan,
     export_shortpaths,
     update_shortpath,
-    show_shortpaths, FindKeyIndexMapExt, fold_shortpath, update_shortpath_path,
+    show_shortpaths, FindKeyIndexMapExt, fold_shortpath, fold_shortpath_for, update_shortpath_path,
 };
 
-use std::path::PathBuf;
+use std::path::{absolute, Path, PathBuf};
 use std::process::exit;
 
 use log::{info, debug};
-use clap::Parser;
+use clap::{CommandFactory, Parser, ValueEnum, error::ErrorKind};
+
+/// Exit before saving names that would break exports or the user's shell
+fn refuse_dangerous_name(name: &str, force: bool) {
+    let dangerous: Vec<Lint> = lint_name(name).into_iter().filter(Lint::is_dangerous).collect();
+    if dangerous.is_empty() || force {
+        return;
+    }
+    dangerous.iter().for_each(|lint| eprintln!("{}", lint));
+    eprintln!("Use --force to save it anyway");
+    exit(1);
+}
+
+/// Get the fully qualified path of a file, even after it was moved or removed
+fn qualify_path(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().or_else(|_| absolute(path)).ok()
+}
 
 fn main() {
     let cli = CLI::parse();
@@ -33,41 +57,122 @@ fn main() {
     info!("Current App Shortpaths:\n{}", toml::to_string_pretty(&shortpaths).expect("Could not serialize."));
 
     match cli.command {
-        Some(Commands::Add { name, path} ) => {
+        Some(Commands::Add { name, path, force, fold } ) => {
+            refuse_dangerous_name(&name, force);
             add_shortpath(&mut shortpaths, name.clone(), path.clone());
-            paths.shortpaths = shortpaths;
-            println!("Saved shortpath {}: {}", name, path.display());
+            if fold {
+                let full_path = absolute_path(&path);
+                let env_vars = paths.env_vars.as_ref().map(|evars| &evars.vars);
+                let folded = fold_shortpath_for(Some(&name), full_path.clone(), &shortpaths, env_vars);
+                update_shortpath_path(&name, folded, Some(full_path), &mut shortpaths);
+            }
+            println!("Saved shortpath {}: {}", name, shortpaths[&name].path.display());
+            paths.set_shortpaths(shortpaths);
+            update_repos(&paths.shortpaths, &mut paths.repos);
+        }
+        Some(Commands::Fold { path }) => {
+            let env_vars = paths.env_vars.as_ref().map(|evars| &evars.vars);
+            let folded = fold_shortpath_for(None, absolute_path(&path), &shortpaths, env_vars);
+            println!("{}", folded.display());
+            exit(0);
         }
         Some(Commands::Remove { names, yes}) => {
             let removed = remove_shortpath(&mut shortpaths, names.as_slice(), yes);
-            paths.shortpaths = shortpaths;
-            for (name, sp) in names.iter().zip(removed.into_iter()) {
+            paths.set_shortpaths(shortpaths);
+            for (name, sp) in names.iter().zip(removed) {
                 let sp = sp.unwrap();
                 println!("Removed {}: {}", name, sp.path.display());
             }
         }
-        Some(Commands::Check {  }) => {
-            check_shortpaths(&mut shortpaths);
+        Some(Commands::Check { format }) => {
+            let report = check_shortpaths(&shortpaths, &paths.resolve);
+            let config = paths.cfg.as_ref().map(|cfg| cfg.path.as_path());
+            show_check_report(&report, format, config);
+            exit(report.exit_code());
+        }
+        Some(Commands::Lint { fix }) => {
+            let lints = lint_shortpaths(&shortpaths);
+            let fixed = if fix { fix_lints(&mut shortpaths, &lints) } else { vec![] };
+            lints.iter().for_each(|lint| {
+                let status = if fixed.contains(lint) { "fixed" } else { "lint" };
+                println!("{}: {}", status, lint);
+            });
+            println!("Lint Complete: {} lints, {} fixed", lints.len(), fixed.len());
+            if !fix {
+                exit(if lints.is_empty() { 0 } else { 1 });
+            }
+            paths.set_shortpaths(shortpaths);
+        }
+        Some(Commands::Refactor { yes }) => {
+            let refactor = plan_refactor(&shortpaths);
+            if refactor.is_empty() {
+                println!("Nothing to refactor");
+                exit(0);
+            }
+            print!("{}", refactor.to_diff());
+            let accepted = yes || prompt_until_valid("Apply these changes? [yes/no]: ", |input| {
+                matches!(input.trim_end(), "yes" | "no")
+            }).is_some_and(|input| input.trim_end() == "yes");
+            if !accepted {
+                exit(0);
+            }
+            apply_refactor(&mut shortpaths, &refactor);
+            paths.set_shortpaths(shortpaths);
         }
         Some(Commands::Show { names }) => {
             show_shortpaths(&paths, names);
         }
-        Some(Commands::Resolve { resolve_type, mode, dry_run }) => {
-            resolve(&mut shortpaths, resolve_type, mode, dry_run);
-            paths.shortpaths = shortpaths;
+        Some(Commands::Resolve { resolve_type, mode, dry_run, format, apply, rewrite_descendants }) => {
+            let cfg = ResolveConfig { rewrite_descendants: paths.resolve.rewrite_descendants || rewrite_descendants, ..paths.resolve.clone() };
+            let plan = match apply {
+                Some(plan_file) => ResolvePlan::read(&plan_file).unwrap_or_else(|e| {
+                    eprintln!("Could not read resolve plan {}: {}", plan_file.display(), e);
+                    exit(1);
+                }),
+                None => plan_resolve(&shortpaths, &paths.repos, resolve_type, mode, &cfg),
+            };
+            if dry_run {
+                show_resolve_plan(&plan, format);
+                exit(0);
+            }
+            if plan.is_empty() {
+                println!("No unreachable paths could be resolved");
+            }
+            let applied = apply_resolve_plan(&mut shortpaths, &plan);
+            paths.set_shortpaths(shortpaths);
+            update_repos(&paths.shortpaths, &mut paths.repos);
+            for entry in applied {
+                match entry.action {
+                    ResolveAction::Update => println!("Resolved {}: {} -> {}", entry.name, entry.previous.display(), entry.updated.display()),
+                    ResolveAction::Restore { .. } => println!("Restored {}: {}", entry.name, entry.full_path.display()),
+                    ResolveAction::Remove => println!("Removed {}: {}", entry.name, entry.previous.display()),
+                }
+            }
         }
-        Some(Commands::Export { export_type, output_file }) => {
-            println!("{:?}", export_type);
-            let dest = export_shortpaths(&shortpaths, export_type, output_file);
-            println!("Exported shell completions to {}", dest.display());
+        Some(Commands::Export { export_type, output_file, jump, metadata, ignore_case, install }) => {
+            if install && get_exporter(export_type).get_install_path().is_none() {
+                let name = export_type.to_possible_value().unwrap();
+                let message = format!("{} exports can't be installed, use --output-file instead", name.get_name());
+                CLI::command().error(ErrorKind::ArgumentConflict, message).exit();
+            }
+            debug!("Export Type: {:?}", export_type);
+            let options = ExportOptions { jump, metadata, ignore_case };
+            let dest = export_shortpaths(&shortpaths, export_type, output_file, options, install).unwrap_or_else(|e| {
+                eprintln!("Could not export shortpaths: {}", e);
+                exit(1);
+            });
+            println!("Exported shortpaths to {}", dest.display());
         }
-        Some(Commands::Update { current_name, name, path }) => {
+        Some(Commands::Update { current_name, name, path, force }) => {
             if name.is_none() && path.is_none() {
                 println!("Shortpath name or path must be provided");
                 exit(1);
             }
-            update_shortpath(&mut shortpaths, &current_name, name, path);
-            paths.shortpaths = shortpaths;
+            if let Some(name) = &name {
+                refuse_dangerous_name(name, force);
+            }
+            update_shortpath(&mut paths, &current_name, name, path);
+            update_repos(&paths.shortpaths, &mut paths.repos);
         }
         Some(Commands::Hook { hook }) => {
             // NOTE: These will fail since the find_key_for_value function does
@@ -82,23 +187,20 @@ fn main() {
 
                     // Get the fully qualified file paths for the inputs
                     let filepaths = filepaths.unwrap();
-                    let filepaths: Vec<PathBuf> = filepaths.into_iter().map(|path| {
-                        path.canonicalize().unwrap()
-                    }).collect();
+                    let filepaths: Vec<PathBuf> = filepaths.iter()
+                        .filter_map(|path| qualify_path(path))
+                        .collect();
                     debug!("Canonicalized Paths: {:?}", filepaths);
 
                     // Filter only shortpath definitions
                     let filepaths: Vec<PathBuf> = filepaths.into_iter().filter(|path| {
-                        let path = path.to_str().unwrap();
                         shortpaths.find_key_for_value(path).is_none()
                     }).collect();
                     debug!("Filtered Shortpaths: {:?}", filepaths);
 
                     // Fold the resulting shortpaths
-                    let filepaths: Vec<String> = filepaths.into_iter().map(|p| {
-                        let folded = fold_shortpath(p, &shortpaths);
-                        let folded = folded.to_str().unwrap();
-                        String::from(folded)
+                    let filepaths: Vec<PathBuf> = filepaths.into_iter().map(|p| {
+                        fold_shortpath(p, &shortpaths)
                     }).collect();
                     debug!("Folded Shortpaths: {:?}", filepaths);
 
@@ -112,21 +214,24 @@ fn main() {
                         None
                     }).collect();
                     debug!("Key Names of Shortpaths: {:?}", names);
-                    let removed = remove_shortpath(&mut shortpaths, names.as_slice(), true);
-                    paths.shortpaths = shortpaths;
-                    debug!("Removed: {:?}", removed);
-
-                    // Display results to user
-                    for (name, sp) in names.iter().zip(removed.into_iter()) {
-                        let sp = sp.unwrap();
-                        println!("Removed {}: {}", name, sp.path.display());
+
+                    // Only the dependents of the removed shortpaths are re-expanded
+                    for name in names.iter() {
+                        let (removed, reexpanded) = paths.remove_path(name);
+                        debug!("Re-expanded: {:?}", reexpanded);
+                        if let Some(sp) = removed {
+                            println!("Removed {}: {}", name, sp.path.display());
+                        }
                     }
                 }
                 Some(Hooks::Move { src, dest }) => {
                     debug!("Given: {}", src.display());
                     debug!("Given: {}", dest.display());
                     // Get the fully qualified file paths
-                    let src = src.canonicalize().unwrap();
+                    let src = match qualify_path(&src) {
+                        Some(src) => src,
+                        None => exit(1),
+                    };
                     debug!("{}", src.display());
 
                     let mut new_dest = src.clone();
@@ -139,16 +244,15 @@ fn main() {
                     // Requires the folded variant of the full_path
                     let spclone = shortpaths.clone(); 
                     let folded = fold_shortpath(src, &shortpaths);
-                    let folded = folded.to_str().unwrap();
-                    debug!("folded: {}", folded);
+                    debug!("folded: {}", folded.display());
 
                     // Update the shortpath definition
-                    let key = spclone.find_key_for_value(folded);
+                    let key = spclone.find_key_for_value(&folded);
                     debug!("key: {:?}", key);
                     if let Some(key) = key {
-                        let folded = fold_shortpath(dest.clone(), &shortpaths);
-                        update_shortpath_path(key, folded, Some(dest), &mut shortpaths);
-                        paths.shortpaths = shortpaths;
+                        let folded = fold_shortpath(dest, &shortpaths);
+                        let reexpanded = paths.update_path(key, folded);
+                        debug!("Re-expanded: {:?}", reexpanded);
                     }
                 }
                 _ => {}
@@ -156,6 +260,8 @@ fn main() {
         }
         _ => {}
     }
+    // Forget the repositories of removed shortpaths, without reading any repository
+    paths.repos.retain(|name, _| paths.shortpaths.contains_key(name));
     paths.shortpaths.sort_paths_inplace();
     to_disk(paths);
     exit(0);
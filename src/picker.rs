@@ -0,0 +1,306 @@
+use crate::{
+    resolve::{Candidates, ResolveAction, ResolveCandidate, ResolveEntry, ResolveStrategy},
+    shortpaths::SP,
+};
+
+use std::{
+    fs::read_dir,
+    io::{self, stderr, Stderr},
+    path::PathBuf,
+};
+
+use crossterm::{
+    cursor::Show,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use indexmap::IndexMap;
+use log::info;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+// Constant Strings
+pub const PICKER_HELP: &str = "↑/↓ candidate  ←/→ shortpath  Enter choose  Del skip  type to filter  Ctrl-S apply  Esc cancel";
+
+// Data Types
+
+/// An unreachable shortpath and its ranked candidates
+#[derive(Debug, Clone)]
+pub struct PickerEntry {
+    pub name: String,
+    pub previous: PathBuf,
+    pub candidates: Vec<ResolveCandidate>,
+}
+
+/// What the picker should do after handling a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerEvent {
+    Continue,
+    Apply,
+    Cancel,
+}
+
+/** Interactive picker state for manually resolving shortpaths
+  *
+  * Every unreachable shortpath is listed next to its ranked candidates,
+  * which can be narrowed down with a fuzzy filter. The chosen candidates
+  * are only returned once the user applies them, all at once.
+  */
+pub struct Picker {
+    pub title: &'static str,
+    pub entries: Vec<PickerEntry>,
+    /// The chosen candidate of each entry, if any
+    pub chosen: Vec<Option<usize>>,
+    /// The highlighted entry
+    pub selected: usize,
+    /// The highlighted candidate, as an index into the filtered candidates
+    pub cursor: usize,
+    pub query: String,
+    matcher: SkimMatcherV2,
+}
+
+// Data Type Implementations
+
+impl Picker {
+    pub fn new(unreachable: &SP, candidates: &Candidates) -> Picker {
+        let entries: Vec<PickerEntry> = candidates.iter().map(|(name, ranked)| {
+            PickerEntry {
+                name: name.to_owned(),
+                previous: unreachable[name].path.to_owned(),
+                candidates: ranked.to_owned(),
+            }
+        }).collect();
+        let chosen = vec![None; entries.len()];
+        Picker { title: "Unreachable", entries, chosen, selected: 0, cursor: 0, query: String::new(), matcher: SkimMatcherV2::default() }
+    }
+
+    /** Create a picker for the rewrites of the shortpaths under moved shortpaths
+      *
+      * Every rewrite is chosen by default, and can be skipped with Del.
+      */
+    pub fn descendants(descendants: &IndexMap<String, Vec<ResolveEntry>>) -> Picker {
+        let entries: Vec<PickerEntry> = descendants.values().flatten().map(|entry| {
+            PickerEntry {
+                name: entry.name.to_owned(),
+                previous: entry.previous.to_owned(),
+                candidates: vec![ResolveCandidate {
+                    full_path: entry.full_path.to_owned(),
+                    score: entry.score,
+                    strategy: ResolveStrategy::Descendant,
+                    action: ResolveAction::Update,
+                }],
+            }
+        }).collect();
+        let chosen = vec![Some(0); entries.len()];
+        Picker { title: "Descendants", entries, chosen, selected: 0, cursor: 0, query: String::new(), matcher: SkimMatcherV2::default() }
+    }
+
+    /// Get the candidates of the highlighted entry that match the filter
+    /// Returns indices into the entry's candidates, with the best matches first
+    pub fn filtered(&self) -> Vec<usize> {
+        let entry = match self.entries.get(self.selected) {
+            Some(entry) => entry,
+            None => return vec![],
+        };
+        if self.query.is_empty() {
+            return (0..entry.candidates.len()).collect();
+        }
+        let mut matches: Vec<(i64, usize)> = entry.candidates.iter().enumerate()
+            .filter_map(|(i, candidate)| {
+                let path = candidate.full_path.display().to_string();
+                self.matcher.fuzzy_match(&path, &self.query).map(|score| (score, i))
+            }).collect();
+        matches.sort_by(|(s1, i1), (s2, i2)| s2.cmp(s1).then(i1.cmp(i2)));
+        matches.into_iter().map(|(_, i)| i).collect()
+    }
+
+    /// Get the highlighted candidate
+    pub fn highlighted(&self) -> Option<&ResolveCandidate> {
+        let i = *self.filtered().get(self.cursor)?;
+        self.entries[self.selected].candidates.get(i)
+    }
+
+    fn select(&mut self, selected: usize) {
+        self.selected = selected;
+        self.cursor = 0;
+        self.query.clear();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerEvent {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let last = self.entries.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('s') if ctrl => return PickerEvent::Apply,
+            KeyCode::Char('c') if ctrl => return PickerEvent::Cancel,
+            KeyCode::Esc if self.query.is_empty() => return PickerEvent::Cancel,
+            KeyCode::Esc => { self.query.clear(); self.cursor = 0; }
+            KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down => self.cursor = (self.cursor + 1).min(self.filtered().len().saturating_sub(1)),
+            KeyCode::Left | KeyCode::BackTab => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Right | KeyCode::Tab => self.select((self.selected + 1).min(last)),
+            KeyCode::Enter => {
+                if let Some(&i) = self.filtered().get(self.cursor) {
+                    self.chosen[self.selected] = Some(i);
+                    self.select((self.selected + 1).min(last));
+                }
+            }
+            KeyCode::Delete => self.chosen[self.selected] = None,
+            KeyCode::Backspace => { self.query.pop(); self.cursor = 0; }
+            KeyCode::Char(c) if !ctrl => { self.query.push(c); self.cursor = 0; }
+            _ => {}
+        }
+        PickerEvent::Continue
+    }
+
+    /// Get the chosen candidate of every entry
+    pub fn choices(&self) -> IndexMap<String, ResolveCandidate> {
+        self.entries.iter().zip(self.chosen.iter())
+            .filter_map(|(entry, chosen)| Some((entry.name.to_owned(), entry.candidates[(*chosen)?].to_owned())))
+            .collect()
+    }
+
+    /// List the contents of the highlighted candidate's directory
+    pub fn preview(&self) -> Vec<String> {
+        let candidate = match self.highlighted() {
+            Some(candidate) => candidate,
+            None => return vec![],
+        };
+        let dir = match &candidate.action {
+            ResolveAction::Restore { trashed, .. } => trashed,
+            _ => &candidate.full_path,
+        };
+        let mut files: Vec<String> = match read_dir(dir) {
+            Ok(files) => files.filter_map(Result::ok).map(|file| {
+                let name = file.file_name().to_string_lossy().to_string();
+                if file.path().is_dir() { format!("{}/", name) } else { name }
+            }).collect(),
+            Err(_) => vec![],
+        };
+        files.sort();
+        files
+    }
+}
+
+// Rendering
+
+fn render(frame: &mut Frame, picker: &Picker) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(rows[0]);
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    // Unreachable shortpaths, or the shortpaths to rewrite
+    let entries: Vec<ListItem> = picker.entries.iter().zip(picker.chosen.iter()).map(|(entry, chosen)| {
+        let mark = if chosen.is_some() { "✔" } else { " " };
+        ListItem::new(vec![
+            Line::from(format!("{} {}", mark, entry.name)),
+            Line::from(format!("  {}", entry.previous.display())),
+        ])
+    }).collect();
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+    let entries = List::new(entries)
+        .block(Block::default().borders(Borders::ALL).title(picker.title))
+        .highlight_style(highlight);
+    frame.render_stateful_widget(entries, columns[0], &mut state);
+
+    // Ranked candidates
+    let chosen = picker.chosen.get(picker.selected).copied().flatten();
+    let candidates: Vec<ListItem> = picker.filtered().into_iter().map(|i| {
+        let candidate = &picker.entries[picker.selected].candidates[i];
+        let mark = if chosen == Some(i) { "*" } else { " " };
+        ListItem::new(format!("{} {:.2} {:<8} {:<7} {}", mark, candidate.score, candidate.strategy,
+            candidate.action, candidate.full_path.display()))
+    }).collect();
+    let title = if picker.query.is_empty() { String::from("Candidates") } else { format!("Candidates (filter: {})", picker.query) };
+    let mut state = ListState::default().with_selected(Some(picker.cursor));
+    let candidates = List::new(candidates)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(highlight);
+    frame.render_stateful_widget(candidates, columns[1], &mut state);
+
+    // Candidate directory preview
+    let preview: Vec<Line> = picker.preview().into_iter().map(Line::from).collect();
+    let preview = Paragraph::new(preview).block(Block::default().borders(Borders::ALL).title("Preview"));
+    frame.render_widget(preview, rows[1]);
+
+    frame.render_widget(Paragraph::new(PICKER_HELP), rows[2]);
+}
+
+fn run_picker(terminal: &mut Terminal<CrosstermBackend<Stderr>>, picker: &mut Picker) -> io::Result<PickerEvent> {
+    loop {
+        terminal.draw(|frame| render(frame, picker))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match picker.handle_key(key) {
+                PickerEvent::Continue => continue,
+                event => return Ok(event),
+            }
+        }
+    }
+}
+
+/// Restores the terminal when dropped, so it is restored even if the picker fails to start
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(stderr(), LeaveAlternateScreen, Show);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Run the picker until the user applies or cancels their choices
+fn pick(picker: &mut Picker) -> io::Result<PickerEvent> {
+    enable_raw_mode()?;
+    let guard = TerminalGuard;
+    execute!(stderr(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stderr()))?;
+    let result = run_picker(&mut terminal, picker);
+
+    // Always restore the terminal, even if the picker failed
+    drop(guard);
+    result
+}
+
+/** Let the user choose candidates for unreachable shortpaths in a full screen picker
+  *
+  * The picker is drawn on stderr so that plans can still be written to stdout.
+  * Returns no choices if the user cancels.
+  */
+pub fn pick_candidates(unreachable: &SP, candidates: &Candidates) -> io::Result<IndexMap<String, ResolveCandidate>> {
+    info!("pick_candidates()");
+    let mut picker = Picker::new(unreachable, candidates);
+    match pick(&mut picker)? {
+        PickerEvent::Apply => Ok(picker.choices()),
+        _ => Ok(IndexMap::new()),
+    }
+}
+
+/** Let the user choose which shortpaths under moved shortpaths are rewritten to use their alias
+  *
+  * Returns the accepted rewrites, or none if the user cancels.
+  */
+pub fn pick_descendants(descendants: &IndexMap<String, Vec<ResolveEntry>>) -> io::Result<Vec<ResolveEntry>> {
+    info!("pick_descendants()");
+    let mut picker = Picker::descendants(descendants);
+    if pick(&mut picker)? != PickerEvent::Apply {
+        return Ok(vec![]);
+    }
+    let choices = picker.choices();
+    Ok(descendants.values().flatten().filter(|entry| choices.contains_key(&entry.name)).cloned().collect())
+}
@@ -0,0 +1,168 @@
+use crate::{
+    lint::{lint_name, sanitize_name},
+    shortpaths::{SP, Shortpath, alias_chain, get_alias_dependency},
+};
+
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use log::{debug, info};
+
+// Constants
+/// The number of shortpaths that must share a directory before it becomes a base alias
+pub const MIN_SHARED: usize = 2;
+
+// Data Types
+
+/** Suggested base aliases for shortpaths with overlapping literal paths
+  *
+  * Shortpaths that share a base alias only need the base alias to be updated
+  * when the shared directory is moved, instead of every shortpath under it.
+  */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Refactor {
+    /// The new base aliases
+    pub added: SP,
+    /// The shortpaths that use the base aliases, with their previous and updated paths
+    pub rewritten: IndexMap<String, (PathBuf, PathBuf)>,
+}
+
+// Data Type Implementations
+
+impl Refactor {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.rewritten.is_empty()
+    }
+
+    /// Format the changes as a diff of the shortpaths config
+    pub fn to_diff(&self) -> String {
+        let mut diff = String::new();
+        self.added.iter().for_each(|(name, sp)| {
+            diff += &format!("+ {} = {}\n", name, sp.path.display());
+        });
+        self.rewritten.iter().for_each(|(name, (previous, updated))| {
+            diff += &format!("- {} = {}\n", name, previous.display());
+            diff += &format!("+ {} = {}\n", name, updated.display());
+        });
+        diff
+    }
+}
+
+// Analysis
+
+/// Check if a shortpath is a literal path, without any aliases
+fn is_literal(sp: &Shortpath) -> bool {
+    get_alias_dependency(sp).is_none() && !sp.path.to_string_lossy().starts_with('$')
+}
+
+/// Directories that are too broad to become base aliases
+/// These are the root directory, the home directory and its ancestors
+fn is_too_broad(dir: &Path) -> bool {
+    let home = dirs::home_dir().unwrap_or_default();
+    dir.components().count() < 3 || home.starts_with(dir)
+}
+
+/// Find a name for a new base alias that isn't taken, and is safe to export
+fn unique_name(dir: &Path, shortpaths: &SP, added: &SP) -> String {
+    let base = sanitize_name(&dir.file_name().unwrap_or_default().to_string_lossy());
+    let is_free = |name: &str| !shortpaths.contains_key(name) && !added.contains_key(name) && lint_name(name).is_empty();
+    if is_free(&base) {
+        return base;
+    }
+    (2..).map(|i| format!("{}_{}", base, i)).find(|name| is_free(name)).unwrap()
+}
+
+/** Find common ancestor directories shared by several literal shortpaths
+  *
+  * Only the deepest directory of every group of shortpaths is chosen,
+  * so shortpaths are rewritten to use their most specific base alias.
+  */
+pub fn find_shared_dirs(shortpaths: &SP) -> IndexMap<PathBuf, Vec<String>> {
+    let mut shared: IndexMap<PathBuf, Vec<String>> = IndexMap::new();
+    for (name, sp) in shortpaths.iter().filter(|(_, sp)| is_literal(sp)) {
+        let full_path = match &sp.full_path {
+            Some(full_path) => full_path,
+            None => continue,
+        };
+        full_path.ancestors().skip(1)
+            .take_while(|dir| !is_too_broad(dir))
+            .for_each(|dir| shared.entry(dir.to_path_buf()).or_default().push(name.to_owned()));
+    }
+    shared.retain(|_, names| names.len() >= MIN_SHARED);
+
+    let deepest: Vec<PathBuf> = shared.iter()
+        .filter(|(dir, names)| !shared.iter().any(|(other, others)| {
+            other != *dir && other.starts_with(dir) && others.len() == names.len()
+        }))
+        .map(|(dir, _)| dir.to_owned())
+        .collect();
+    shared.retain(|dir, _| deepest.contains(dir));
+    debug!("Shared Directories: {:?}", shared);
+    shared
+}
+
+/** Propose base aliases for the directories shared by several literal shortpaths
+  *
+  * Existing shortpaths that already point to a shared directory are reused as the base alias.
+  * New base aliases are nested under other base aliases when they can be.
+  */
+pub fn plan_refactor(shortpaths: &SP) -> Refactor {
+    info!("plan_refactor()");
+    let mut refactor = Refactor::default();
+    let shared = find_shared_dirs(shortpaths);
+
+    // Choose a base alias for every shared directory
+    let mut bases: Vec<(PathBuf, String)> = vec![];
+    for dir in shared.keys() {
+        let existing = shortpaths.iter().find(|(_, sp)| sp.full_path.as_ref() == Some(dir));
+        let name = match existing {
+            Some((name, _)) => name.to_owned(),
+            None => {
+                let name = unique_name(dir, shortpaths, &refactor.added);
+                refactor.added.insert(name.to_owned(), Shortpath::new(dir.to_owned(), Some(dir.to_owned())));
+                name
+            }
+        };
+        bases.push((dir.to_owned(), name));
+    }
+    // Prefer the most specific base alias
+    bases.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+
+    let rebase = |name: &str, full_path: &Path| -> Option<PathBuf> {
+        let (dir, base) = bases.iter().find(|(dir, base)| {
+            full_path != dir && full_path.starts_with(dir) && base != name
+                && alias_chain(base, shortpaths).map_or(true, |chain| !chain.iter().any(|dep| dep == name))
+        })?;
+        Some(PathBuf::from(format!("${}", base)).join(full_path.strip_prefix(dir).ok()?))
+    };
+
+    // Nest the new base aliases
+    let added: Vec<(String, PathBuf)> = refactor.added.iter()
+        .map(|(name, sp)| (name.to_owned(), sp.full_path.to_owned().unwrap()))
+        .collect();
+    for (name, full_path) in added {
+        if let Some(updated) = rebase(&name, &full_path) {
+            refactor.added[&name].path = updated;
+        }
+    }
+
+    // Rewrite the literal shortpaths under the base aliases
+    for (name, sp) in shortpaths.iter().filter(|(_, sp)| is_literal(sp)) {
+        let updated = sp.full_path.as_ref().and_then(|full_path| rebase(name, full_path));
+        if let Some(updated) = updated {
+            refactor.rewritten.insert(name.to_owned(), (sp.path.to_owned(), updated));
+        }
+    }
+    refactor
+}
+
+/// Add the base aliases and rewrite their dependents
+pub fn apply_refactor(shortpaths: &mut SP, refactor: &Refactor) {
+    info!("apply_refactor()");
+    shortpaths.extend(refactor.added.to_owned());
+    for (name, (_, updated)) in refactor.rewritten.iter() {
+        if let Some(sp) = shortpaths.get_mut(name) {
+            sp.path = updated.to_owned();
+        }
+    }
+}
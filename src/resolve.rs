@@ -0,0 +1,237 @@
+use crate::{
+    escape::serde_path,
+    helpers::{expand_tilde, tab_align},
+    trash::trash_dir,
+};
+
+use std::{
+    fmt,
+    fs::read_to_string,
+    io,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+
+// Data Types
+
+/// The search strategy that produced a resolve candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolveStrategy {
+    Matching,
+    Trash,
+    Git,
+    Descendant,
+}
+
+/// The change to make to an unreachable shortpath
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ResolveAction {
+    /// Point the shortpath at the new path
+    #[default]
+    Update,
+    /// Move the trashed file back to the shortpath
+    Restore {
+        #[serde(with = "serde_path")]
+        trashed: PathBuf,
+        #[serde(with = "serde_path")]
+        info: PathBuf,
+    },
+    /// Remove the shortpath
+    Remove,
+}
+
+/// A possible fix for an unreachable shortpath
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveCandidate {
+    /// The new expanded on disk path
+    pub full_path: PathBuf,
+    /// How likely the candidate is the moved path, from 0.0 to 1.0
+    pub score: f64,
+    pub strategy: ResolveStrategy,
+    pub action: ResolveAction,
+}
+
+/// The ranked candidates of every unreachable shortpath, best candidates first
+pub type Candidates = IndexMap<String, Vec<ResolveCandidate>>;
+
+/// A single planned change to an unreachable shortpath
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolveEntry {
+    /// The shortpath alias name
+    pub name: String,
+    /// The shortpath path before the change
+    #[serde(with = "serde_path")]
+    pub previous: PathBuf,
+    /// The new folded shortpath path
+    #[serde(with = "serde_path")]
+    pub updated: PathBuf,
+    /// The new expanded on disk path
+    #[serde(with = "serde_path")]
+    pub full_path: PathBuf,
+    /// How likely the candidate is the moved path, from 0.0 to 1.0
+    pub score: f64,
+    pub strategy: ResolveStrategy,
+    #[serde(default)]
+    pub action: ResolveAction,
+}
+
+/** The set of changes `resolve` will make to the shortpaths config
+  *
+  * Plans are created by `plan_resolve`, and can be written to disk
+  * with `--dry-run --format json` to be reviewed before being applied
+  * with `resolve --apply`.
+  */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResolvePlan {
+    pub entries: Vec<ResolveEntry>,
+}
+
+/** Options to bound the directories searched by resolve
+  *
+  * These are read from the `[resolve]` table of the shortpaths config:
+  *
+  * ```toml
+  * [resolve]
+  * roots = ["~", "/mnt/data"]
+  * exclude = ["node_modules", "~/.cache"]
+  * max_depth = 6
+  * follow_symlinks = false
+  * trash_dir = "~/.local/share/Trash"
+  * ```
+  *
+  * Without any roots, only the parent directories of unreachable shortpaths are searched.
+  */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResolveConfig {
+    /// Directories to search recursively for moved shortpaths
+    pub roots: Vec<PathBuf>,
+    /// Directory names or paths that are never searched
+    pub exclude: Vec<PathBuf>,
+    /// How many directories deep to search in each root
+    /// Only limits the roots, since only the siblings of unreachable shortpaths
+    /// are searched in their parent directories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// Whether to follow symbolic links while searching
+    pub follow_symlinks: bool,
+    /// The XDG trash to check for deleted shortpaths
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trash_dir: Option<PathBuf>,
+    /// Whether automatic resolves rewrite the literal paths under moved shortpaths to use their alias
+    pub rewrite_descendants: bool,
+}
+
+// Trait Implementations
+
+impl fmt::Display for ResolveStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolveStrategy::Matching => write!(f, "matching"),
+            ResolveStrategy::Trash => write!(f, "trash"),
+            ResolveStrategy::Git => write!(f, "git"),
+            ResolveStrategy::Descendant => write!(f, "descendant"),
+        }
+    }
+}
+
+impl fmt::Display for ResolveAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolveAction::Update => write!(f, "update"),
+            ResolveAction::Restore { .. } => write!(f, "restore"),
+            ResolveAction::Remove => write!(f, "remove"),
+        }
+    }
+}
+
+// Data Type Implementations
+
+impl ResolveConfig {
+    pub fn is_default(&self) -> bool { *self == ResolveConfig::default() }
+
+    /// Get the search roots with '~' expanded to the user's home
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.iter().filter_map(expand_tilde).collect()
+    }
+
+    /// Check if a path should be skipped while searching
+    /// Relative patterns like `node_modules` or `target/debug` match the last components of a path,
+    /// while absolute paths match any path beneath them
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude.iter().any(|pattern| {
+            if !pattern.is_absolute() && !pattern.starts_with("~") {
+                path.ends_with(pattern)
+            } else {
+                expand_tilde(pattern).is_some_and(|pattern| path.starts_with(pattern))
+            }
+        })
+    }
+
+    /// Get the trash directory, defaulting to the user's home trash
+    pub fn trash_dir(&self) -> Option<PathBuf> {
+        match &self.trash_dir {
+            Some(dir) => expand_tilde(dir),
+            None => trash_dir(),
+        }
+    }
+
+    /// Check if a directory lies inside any of the search roots
+    pub fn in_roots(&self, dir: &Path) -> bool {
+        self.roots().iter().any(|root| dir.starts_with(root))
+    }
+}
+
+impl ResolvePlan {
+    pub fn new() -> ResolvePlan { Default::default() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn push(&mut self, entry: ResolveEntry) { self.entries.push(entry) }
+
+    /// Read a previously saved resolve plan
+    pub fn read(path: &Path) -> io::Result<ResolvePlan> {
+        let conts = read_to_string(path)?;
+        serde_json::from_str(&conts).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Could not serialize resolve plan.")
+    }
+
+    /// Horizontally align the planned changes into columns
+    pub fn to_table(&self) -> String {
+        let header = ["NAME", "ACTION", "PREVIOUS", "UPDATED", "SCORE", "STRATEGY"];
+        let rows: Vec<[String; 6]> = self.entries.iter().map(|entry| {
+            [
+                entry.name.to_owned(),
+                entry.action.to_string(),
+                entry.previous.display().to_string(),
+                entry.updated.display().to_string(),
+                format!("{:.2}", entry.score),
+                entry.strategy.to_string(),
+            ]
+        }).collect();
+
+        let widths: Vec<usize> = (0..header.len()).map(|col| {
+            rows.iter().map(|row| row[col].len()).chain([header[col].len()]).max().unwrap()
+        }).collect();
+
+        let format_row = |row: Vec<&str>| {
+            let line: String = row.iter().zip(widths.iter())
+                .map(|(cell, width)| tab_align(cell, *width, Some("  ")))
+                .collect();
+            format!("{}\n", line.trim_end())
+        };
+
+        let mut output = format_row(header.to_vec());
+        rows.iter().for_each(|row| {
+            output += &format_row(row.iter().map(String::as_str).collect());
+        });
+        output
+    }
+}
@@ -1,32 +1,45 @@
-use crate::app::{ExportType, Mode, ResolveType};
+use crate::app::{ExportType, Mode, PlanFormat, ResolveType};
 use crate::builder::{Shortpaths, ShortpathsAlignExt};
-use crate::env::{EnvPathOperationsExt, EnvVars};
-use crate::export::get_exporter;
+use crate::env::{EnvPathOperationsExt, EnvVars, EP};
+use crate::export::{ExportOptions, get_exporter_with};
 use crate::helpers::{
     to_str_slice,
+    expand_tilde,
     search_for,
     matching_file_names,
     in_parent_dir,
-    auto_resolve,
-    manual_resolve, ScopeResults,
+    in_search_roots,
+    prompt_candidates,
+    ScopeResults, ScopeFn,
+    index_scopes, Scope,
     prompt_until_valid,
+    score_candidate,
 };
+use crate::picker::{pick_candidates, pick_descendants};
+use crate::resolve::{Candidates, ResolveAction, ResolveCandidate, ResolveConfig, ResolveEntry, ResolvePlan, ResolveStrategy};
+use crate::escape::serde_path;
+use crate::trash::{read_trash, find_trashed, restore_trashed};
+use crate::git::{Repos, in_repo_scopes, find_repos};
 
 use std::{
+    ffi::OsString,
     path::{Path, PathBuf, Component},
     cmp::Ordering,
-    process::exit,
+    io::{self, stdin, stderr, IsTerminal},
 };
 
 #[allow(unused_imports)]
 use itertools::Itertools;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use log::{trace, debug, info};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
 // Data Types
 pub type SP = IndexMap<String, Shortpath>;
 
+/// The shortpaths that directly depend on each alias, including undefined aliases
+pub type ReverseDeps = IndexMap<String, IndexSet<String>>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ShortpathVariant {
     Independent,
@@ -34,6 +47,26 @@ pub enum ShortpathVariant {
     Environment,
 }
 
+/// Errors in the aliases a shortpath depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasError {
+    /// The alias is not defined
+    Undefined(String),
+    /// The aliases depend on each other in a cycle
+    Cycle(Vec<String>),
+}
+
+/** An alias that paths can be folded into
+  *
+  * Shortpath aliases are ordered before environment variables,
+  * so they are preferred when both fold the same prefix.
+  */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FoldAlias {
+    Shortpath(String),
+    Env(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Shortpath {
     pub path: PathBuf,
@@ -43,12 +76,13 @@ pub struct Shortpath {
 // Trait Implementations
 
 // Serialize Shortpath as &str
+// Paths that aren't valid UTF-8 are escaped, see `serde_path`
 impl Serialize for Shortpath {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.path.to_str().unwrap())
+        serde_path::serialize(&self.path, serializer)
     }
 }
 
@@ -58,8 +92,8 @@ impl<'de> Deserialize<'de> for Shortpath {
     where
         D: Deserializer<'de>,
     {
-        let path: &str = Deserialize::deserialize(deserializer)?;
-        let sp = Shortpath::new(PathBuf::from(path), None);
+        let path = serde_path::deserialize(deserializer)?;
+        let sp = Shortpath::new(path, None);
         Ok(sp)
     }
 }
@@ -100,23 +134,23 @@ pub trait FindKeyIndexMapExt<'a, K, V> {
 
 impl<'a, V> FindKeyIndexMapExt<'a, String, V> for IndexMap<String, Shortpath>
 where
-    V: Into<String>
+    V: AsRef<Path>
 {
     fn find_keys_for_value(&'a self, value: V) -> Vec<&'a String> {
-        let v = value.into();
+        let v = value.as_ref();
         self.into_iter()
-            .filter_map(|(key, val)| if val.path.to_str().unwrap() == v { Some(key) } else { None })
+            .filter_map(|(key, val)| if val.path == v { Some(key) } else { None })
             .collect()
     }
 
     fn find_key_for_value(&'a self, value: V) -> Option<&'a String> {
-        let v = value.into();
-        self.iter().find_map(|(key, val)| if val.path.to_str().unwrap() == v { Some(key) } else { None })
+        let v = value.as_ref();
+        self.iter().find_map(|(key, val)| if val.path == v { Some(key) } else { None })
     }
 
     fn find_key_for_full_path(&'a self, value: V) -> Option<&'a String> {
-        let v = value.into();
-        self.iter().find_map(|(key, val)| if val.full_path.as_ref()?.to_str().unwrap() == v { Some(key) } else { None })
+        let v = value.as_ref();
+        self.iter().find_map(|(key, val)| if val.full_path.as_ref()? == v { Some(key) } else { None })
     }
 }
 
@@ -190,21 +224,19 @@ pub fn parse_env_alias(comp: String) -> Option<String> {
 /// Replaces ${env:PATH} -> $PATHs
 pub fn substitute_env_paths(shorpaths: SP) -> SP {
     shorpaths.into_iter().map(|(name, mut sp)| {
-        let mut path = sp.path.to_str().unwrap().to_owned();
-        sp.path.components().for_each(|comp| {
-            let compstr = comp.as_os_str().to_str().unwrap().to_owned();
-            let alias = parse_env_alias(compstr);
-            if let Some(env_name) = alias {
-                path = substitute_env_alias_name(&path, &env_name);
+        sp.path = sp.path.components().map(|comp| {
+            match parse_env_alias(to_string(&comp)) {
+                Some(env_name) => OsString::from(format!("${}", env_name)),
+                None => comp.as_os_str().to_owned(),
             }
-        });
-        sp.path = PathBuf::from(path);
+        }).collect();
         (name, sp)
     }).collect()
 }
 
+/// Get a path component as a string, replacing any invalid UTF-8
 pub fn to_string(comp: &Component) -> String {
-    comp.as_os_str().to_str().unwrap().to_string()
+    comp.as_os_str().to_string_lossy().into_owned()
 }
 
 pub fn str_join_path(s1: &str, s2: &str) -> PathBuf {
@@ -212,130 +244,232 @@ pub fn str_join_path(s1: &str, s2: &str) -> PathBuf {
     p1.join(p2)
 }
 
-pub fn get_sp_deps(alias_name: String, shortpaths: &SP) -> (String, String) {
-    let sp_depend_name = parse_alias(alias_name).unwrap();
-    debug!("sp_depend_name = {}", &sp_depend_name);
+/// Get the alias a shortpath depends on, if its first component is an alias
+pub fn get_alias_dependency(sp: &Shortpath) -> Option<String> {
+    let comp = to_string(&sp.path.components().next()?);
+    if comp.starts_with("${env:") {
+        return None;
+    }
+    parse_alias(comp)
+}
 
-    let sp_depend_path = shortpaths.get(&sp_depend_name).unwrap();
-    let depend_path = sp_depend_path.path.to_str().unwrap().to_string();
-    debug!("depend_path = {}", &depend_path);
-    (sp_depend_name, depend_path)
+/// Get the shortpaths that directly depend on each alias
+pub fn reverse_dependencies(shortpaths: &SP) -> ReverseDeps {
+    let mut reverse_deps = ReverseDeps::new();
+    for (name, sp) in shortpaths.iter() {
+        if let Some(alias) = get_alias_dependency(sp) {
+            reverse_deps.entry(alias).or_default().insert(name.to_owned());
+        }
+    }
+    reverse_deps
 }
 
-pub fn fold_shortpath(mut path: PathBuf, shortpaths: &SP) -> PathBuf {
-    info!("fold_shortpath()");
-    let mut output = path.to_str().unwrap().to_owned();
-    while let Some(parent) = path.parent() {
-        if let Some(key) = shortpaths.find_key_for_full_path(parent.to_str().unwrap()) {
-            let this = parent.to_str().unwrap();
-            let with = format!("${}", key);
-            output = output.replace(this, &with);
+/** Follow the chain of aliases a shortpath depends on
+  *
+  * Returns the aliases in order from the nearest dependency to the furthest,
+  * or an error if an alias is undefined or depends on itself.
+  */
+pub fn alias_chain(name: &str, shortpaths: &SP) -> Result<Vec<String>, AliasError> {
+    let mut chain: Vec<String> = vec![];
+    let mut next = shortpaths.get(name).and_then(get_alias_dependency);
+    while let Some(alias) = next {
+        if alias == name || chain.contains(&alias) {
+            chain.push(alias);
+            return Err(AliasError::Cycle(chain));
         }
-        path = parent.to_owned();
+        let sp = shortpaths.get(&alias).ok_or_else(|| AliasError::Undefined(alias.to_owned()))?;
+        next = get_alias_dependency(sp);
+        chain.push(alias);
     }
-    PathBuf::from(output)
+    Ok(chain)
 }
 
-/**
-  * Expand shortpath variants at runtime
-  * 
-  * The shortpath dependencies must be populated before this is run. When they are
-  * populated, both their name and path values are stored in the enum variant which
-  * is accessed here without the hashmap.
+/** Order the shortpaths so every alias comes before the shortpaths that depend on it
+  *
+  * Shortpaths that are already in dependency order keep their order.
+  * Shortpaths with broken aliases are left where they are.
   */
-pub fn expand_shortpath(sp: &Shortpath, shortpaths: &SP) -> PathBuf {
-    info!("expand_shortpath()");
+pub fn sort_by_dependencies(shortpaths: SP) -> SP {
+    let mut sorted = SP::new();
+    for name in shortpaths.keys() {
+        let chain = alias_chain(name, &shortpaths).unwrap_or_default();
+        for alias in chain.iter().rev().chain(std::iter::once(name)) {
+            if !sorted.contains_key(alias) {
+                sorted.insert(alias.to_owned(), shortpaths[alias].to_owned());
+            }
+        }
+    }
+    sorted
+}
 
-    // Helper functions for use in expand_shortpath_inner
-    fn get_sp_alias_name_base(comp: Component) -> String { to_string(&comp) }
+/// Environment variables that change too often to fold paths into
+pub const VOLATILE_ENV_VARS: &[&str] = &["PWD", "OLDPWD"];
 
-    /// NOTE: This only works for the first component of a string
-    /// and it's highly likely that in the future, this will have to change
-    /// to support more complex shortpaths (doubly nested paths like $a/$b)
-    fn get_sp_alias_name_recurse(alias_path: String) -> String {
-        let pbuf = PathBuf::from(alias_path);
-        to_string(&pbuf.components().next().unwrap())
+impl FoldAlias {
+    pub fn name(&self) -> &str {
+        match self {
+            FoldAlias::Shortpath(name) | FoldAlias::Env(name) => name,
+        }
     }
 
-    fn get_expanded_path(entry: PathBuf, sp_depend_name: &str, depend_path: &str) -> String {
-        let expanded = expand_path(entry.to_str().unwrap(), sp_depend_name, depend_path);
-        debug!("\tExpand: {} -> {}", entry.display(), &expanded);
-        expanded
+    /// The path component that replaces the folded prefix
+    pub fn to_prefix(&self) -> String {
+        match self {
+            FoldAlias::Shortpath(name) => format!("${}", name),
+            FoldAlias::Env(name) => format!("${{env:{}}}", name),
+        }
     }
+}
 
-    fn expand_layer(alias_name: String, depend_path: String, entry: PathBuf, shortpaths: &SP) -> String {
-        let result = expand_shortpath_inner(alias_name, depend_path, entry, true, shortpaths);
-        debug!("\tResult: {}\n", result);
-        result
+/** Find the alias that folds the longest prefix of a path
+  *
+  * Prefixes are matched per path component, so `/home/a` never folds `/home/ab`.
+  * Only strict ancestors of the path are folded. Ties are broken by preferring
+  * shortpath aliases over environment variables, then shorter names, then the
+  * name in lexicographical order.
+  *
+  * Aliases that depend on `name` are skipped, so folding `name` can't create a cycle.
+  *
+  * For any absolute path `p`, expanding the folded path gives back `p`:
+  * `expand_shortpath(fold_shortpath(p)) == p`, as long as the full paths are up to date.
+  */
+pub fn find_fold(path: &Path, shortpaths: &SP, env_vars: Option<&EP>, name: Option<&str>) -> Option<(FoldAlias, PathBuf)> {
+    if !path.is_absolute() {
+        return None;
     }
+    let depends_on_name = |alias: &str| match name {
+        Some(name) => alias == name || alias_chain(alias, shortpaths).map_or(true, |chain| chain.iter().any(|dep| dep == name)),
+        None => false,
+    };
 
-    pub fn expand_shortpath_inner(alias_name: String, alias_path: String, entry: PathBuf, has_started: bool, shortpaths: &SP) -> String {
-        info!("Inputs");
-        debug!("\talias_path  = {}", &alias_path);
-        debug!("\tentry       = {}", &entry.display());
-        debug!("\talias_name  = {}\n", &alias_name);
+    // Names that don't parse back as a single alias component can't be expanded again
+    let is_foldable = |alias: &str| {
+        let folded = Shortpath::new(PathBuf::from(FoldAlias::Shortpath(alias.to_owned()).to_prefix()), None);
+        folded.path.components().count() == 1 && get_alias_dependency(&folded).as_deref() == Some(alias)
+    };
+    let aliases = shortpaths.iter()
+        .filter(|(alias, _)| is_foldable(alias) && !depends_on_name(alias))
+        .filter_map(|(alias, sp)| Some((FoldAlias::Shortpath(alias.to_owned()), sp.full_path.to_owned()?)));
+    let envs = env_vars.into_iter().flatten()
+        .filter(|(var, _)| Some(var.as_str()) != name && !VOLATILE_ENV_VARS.contains(&var.as_str()))
+        .map(|(var, value)| (FoldAlias::Env(var.to_owned()), PathBuf::from(value)))
+        .filter(|(_, dir)| dir.is_absolute());
+
+    aliases.chain(envs)
+        .filter(|(_, dir)| dir != path && dir.components().count() > 1)
+        .filter_map(|(alias, dir)| {
+            let rest = path.strip_prefix(&dir).ok()?.to_path_buf();
+            Some((dir.components().count(), alias, rest))
+        })
+        .min_by(|(n1, a1, _), (n2, a2, _)| {
+            n2.cmp(n1)
+                .then_with(|| matches!(a1, FoldAlias::Env(_)).cmp(&matches!(a2, FoldAlias::Env(_))))
+                .then_with(|| a1.name().len().cmp(&a2.name().len()))
+                .then_with(|| a1.cmp(a2))
+        })
+        .map(|(_, alias, rest)| (alias, rest))
+}
+
+/// Fold a path into the shortpath alias of its nearest ancestor
+pub fn fold_shortpath(path: PathBuf, shortpaths: &SP) -> PathBuf {
+    info!("fold_shortpath()");
+    fold_shortpath_for(None, path, shortpaths, None)
+}
 
-        let peek_clone = entry.clone();
-        let mut peekable = peek_clone.components().peekable();
-        if has_started && (alias_name.is_empty() || peekable.peek().is_none()) {
-            return alias_path;
-        }
-        // Else Assume we can obtain a component
-        let comp = entry.components().next().unwrap();
-        let comp_slice = to_str_slice(comp.as_os_str().to_str().unwrap());
-        let shortpath_type = get_shortpath_type(&comp_slice);
-
-        // If we have nothing, exit early
-        match &shortpath_type {
-            Some(var) => {
-                match var {
-                    ShortpathVariant::Environment | ShortpathVariant::Independent => return alias_path,
-                    _ => {}
-                }
-            }
-            None => return alias_path
+/// Fold the path of the shortpath `name` into the most specific shortpath alias or environment variable
+pub fn fold_shortpath_for(name: Option<&str>, path: PathBuf, shortpaths: &SP, env_vars: Option<&EP>) -> PathBuf {
+    match find_fold(&path, shortpaths, env_vars, name) {
+        Some((alias, rest)) => {
+            trace!("Folded {} into {}", path.display(), alias.to_prefix());
+            PathBuf::from(alias.to_prefix()).join(rest)
         }
-        // Else Assume we can obtain a variant
-        let shortpath_variant = shortpath_type.unwrap();
+        None => path,
+    }
+}
 
-        let mut expanded = String::new();
-        match shortpath_variant {
-            ShortpathVariant::Environment => {
-                trace!("Environment Variable Detected");
-            }
-            ShortpathVariant::Alias => {
-                if !has_started {
-                    info!("Branch 1: Beginning recursive expansion");
-                    let (sp_depend_name, depend_path)  = get_sp_deps(get_sp_alias_name_base(comp), shortpaths);
-                    expanded = get_expanded_path(entry, &sp_depend_name, &depend_path);
-
-                    return expand_layer(format!("${}", &sp_depend_name), expanded.clone(), PathBuf::from(expanded), shortpaths);
-                } else if let Some(parsed) = parse_alias(alias_name) {
-                    trace!("Branch 2: In recursive expansion");
-                    trace!("Parsed alias_name: {}", &parsed);
-
-                    let (sp_depend_name, depend_path) = get_sp_deps(get_sp_alias_name_recurse(alias_path), shortpaths);
-                    expanded = get_expanded_path(entry, &sp_depend_name, &depend_path);
-                    return expand_layer(sp_depend_name, expanded.clone(), PathBuf::from(expanded), shortpaths);
-                } else {
-                    trace!("Branch 3: Inside Termination Case");
-                    trace!("Alias Path: {}", &alias_path);
+/**
+  * Expand shortpath variants at runtime
+  *
+  * Only the first component of a path can be an alias. It is replaced by the
+  * expanded path of the alias, and the remaining components are kept as is,
+  * so literal components that look like aliases are never expanded.
+  *
+  * Returns an error if an alias is undefined or the aliases form a cycle, like `alias_chain`.
+  */
+pub fn expand_shortpath(sp: &Shortpath, shortpaths: &SP) -> Result<PathBuf, AliasError> {
+    trace!("expand_shortpath()");
+    expand_shortpath_visiting(sp, shortpaths, &mut vec![])
+}
 
-                    let (sp_depend_name, depend_path) = get_sp_deps(get_sp_alias_name_recurse(alias_path), shortpaths);
-                    expanded = get_expanded_path(entry, &sp_depend_name, &depend_path);
-                    trace!("All Layers Expanded");
-                    return expanded;
+/// Expand a shortpath, keeping track of the aliases already followed to detect cycles
+fn expand_shortpath_visiting(sp: &Shortpath, shortpaths: &SP, visited: &mut Vec<String>) -> Result<PathBuf, AliasError> {
+    let alias = match get_alias_dependency(sp) {
+        Some(alias) => alias,
+        None => return Ok(expand_tilde(&sp.path).unwrap_or(sp.path.to_owned())),
+    };
+    if visited.contains(&alias) {
+        visited.push(alias);
+        return Err(AliasError::Cycle(visited.to_owned()));
+    }
+    let dependency = shortpaths.get(&alias).ok_or_else(|| AliasError::Undefined(alias.to_owned()))?;
+    visited.push(alias);
+    let base = expand_shortpath_visiting(dependency, shortpaths, visited)?;
+    let expanded = join_alias_base(base, &sp.path);
+    trace!("\tExpand: {} -> {}", sp.path.display(), expanded.display());
+    Ok(expanded)
+}
+
+/// Replace the alias in the first component of a path with its expanded path
+pub fn join_alias_base(base: PathBuf, path: &Path) -> PathBuf {
+    let rest: PathBuf = path.components().skip(1).collect();
+    if rest.as_os_str().is_empty() { base } else { base.join(rest) }
+}
+
+/** Expand every shortpath at once
+  *
+  * The dependencies of each shortpath are followed until a shortpath that was
+  * already expanded, and the expanded paths are cached on the way back. Every
+  * alias is expanded exactly once, so this runs in linear time in the number
+  * of shortpaths, even for deeply nested aliases.
+  *
+  * Shortpaths with undefined or cyclic aliases, or that depend on one, are `None`.
+  */
+pub fn expand_shortpaths(shortpaths: &SP) -> IndexMap<String, Option<PathBuf>> {
+    info!("expand_shortpaths()");
+    let mut expanded: IndexMap<String, Option<PathBuf>> = IndexMap::with_capacity(shortpaths.len());
+    for name in shortpaths.keys() {
+        // The shortpaths waiting for their dependency to be expanded
+        let mut pending: IndexSet<&str> = IndexSet::new();
+        let mut next = name.as_str();
+        let mut base = loop {
+            if let Some(done) = expanded.get(next) {
+                break done.to_owned();
+            }
+            if pending.contains(next) {
+                break None; // Cyclic
+            }
+            let sp = &shortpaths[next];
+            match get_alias_dependency(sp) {
+                Some(alias) => {
+                    pending.insert(next);
+                    match shortpaths.get_key_value(&alias) {
+                        Some((alias, _)) => next = alias,
+                        None => break None, // Undefined
+                    }
+                }
+                None => {
+                    let literal = expand_tilde(&sp.path).unwrap_or(sp.path.to_owned());
+                    expanded.insert(next.to_owned(), Some(literal.to_owned()));
+                    break Some(literal);
                 }
             }
-            _ => {}
+        };
+        for pending in pending.into_iter().rev() {
+            base = base.map(|base| join_alias_base(base, &shortpaths[pending].path));
+            expanded.insert(pending.to_owned(), base.to_owned());
         }
-        debug!("Expanded: {}", expanded);
-        expanded
     }
-    let entry = sp.path.to_str().unwrap().to_string();
-    let str_path = expand_shortpath_inner(String::new(), entry, sp.path.to_owned(), false, shortpaths);
-
-    PathBuf::from(str_path)
+    expanded
 }
 
 // Commands
@@ -356,8 +490,11 @@ pub fn remove_shortpath(shortpaths: &mut SP, names: &[String], yes: bool) -> Vec
             let path = sp.path.display();
             let message = format!("Remove {} : {}? [yes/no]: ", name, path);
 
-            let input = prompt_until_valid(&message, is_valid_input);
-            match input.as_str() {
+            let input = match prompt_until_valid(&message, is_valid_input) {
+                Some(input) => input,
+                None => break,
+            };
+            match input.trim_end() {
                 "yes" => {
                     let sp = shortpaths.remove(name);
                     removed.push(sp);
@@ -378,18 +515,10 @@ pub fn find_unreachable(shortpaths: &SP) -> IndexMap<String, Shortpath> {
         .filter(|(_, sp)| {
             let full_path = &sp.full_path;
             full_path.is_none() || !full_path.as_ref().unwrap().exists()
-        }).into_iter().map(|(name, sp)| (name.to_owned(), sp.to_owned())).collect();
+        }).map(|(name, sp)| (name.to_owned(), sp.to_owned())).collect();
     unreachable
 }
 
-/// List any broken or unreachable paths
-pub fn check_shortpaths(shortpaths: &mut SP) {
-    let unreachable = find_unreachable(shortpaths);
-    unreachable.iter().for_each(|(alias_name, alias_path)|
-        println!("{} shortpath is unreachable: {}", alias_name, alias_path.path.display()));
-    println!("Check Complete");
-}
-
 /// Displays a shortpath
 /// NOTE: In the future this function could potentially accept another
 /// parameter 'pretty_print'
@@ -454,9 +583,9 @@ pub fn show_search_results(results: &IndexMap<String, ScopeResults>) {
     });
 }
 
-/** Fix unreachable or broken paths
+/** Find candidates to fix unreachable or broken paths
   * 
-  * `resolve` makes use of search and scope functions to provide more
+  * `find_candidates` makes use of search and scope functions to provide more
   *     fine grained searching functionality.
   * 
   * The currently supported execution modes are:
@@ -465,81 +594,355 @@ pub fn show_search_results(results: &IndexMap<String, ScopeResults>) {
   *     - similar_file_names (TODO NOT IMPLEMENTED).
   * Scope Functions: 
   *     - in_parent_dir 
+  *     - in_search_roots : Used when the resolve config has search roots.
   *     - nearest_neighbours (TODO NOT IMPLEMENTED).
+  *
+  * Before searching by file name, trashed shortpaths are offered to be restored from the trash,
+  * and shortpaths with a recorded git repository are searched for by their remote.
+  * Unreachable shortpaths without any candidates are left out, along with undefined
+  * or cyclic aliases, since they have no previous location to search from.
+  */
+pub fn find_candidates(unreachable: &SP, repos: &Repos, resolve_type: ResolveType, cfg: &ResolveConfig) -> Candidates {
+    info!("find_candidates()");
+    let mut candidates = Candidates::new();
+    let mut unreachable = unreachable.to_owned();
+    unreachable.retain(|_, sp| sp.full_path.is_some());
+
+    // Offer to restore trashed shortpaths instead of searching for them
+    let trash = cfg.trash_dir().map(|dir| read_trash(&dir)).unwrap_or_default();
+    unreachable.retain(|name, sp| {
+        let full_path = sp.full_path.to_owned().unwrap();
+        let trashed = match find_trashed(&full_path, &trash) {
+            Some(trashed) => trashed,
+            None => return true,
+        };
+        debug!("{} was trashed on {}", name, trashed.deleted_on());
+        let restore = ResolveAction::Restore { trashed: trashed.trashed.to_owned(), info: trashed.info.to_owned() };
+        let ranked = [restore, ResolveAction::Remove].into_iter().map(|action| {
+            ResolveCandidate { full_path: full_path.to_owned(), score: 1.0, strategy: ResolveStrategy::Trash, action }
+        }).collect();
+        candidates.insert(name.to_owned(), ranked);
+        false
+    });
+
+    // Find moved or re-cloned git repositories by their remote
+    let repo_scopes: IndexMap<String, Vec<Scope>> = unreachable.iter()
+        .filter(|(name, _)| repos.contains_key(*name))
+        .map(|(name, sp)| (name.to_owned(), in_repo_scopes(sp, cfg)))
+        .collect();
+    let git_dirs: IndexSet<OsString> = IndexSet::from([OsString::from(".git")]);
+    let index = index_scopes(repo_scopes.values().flatten().cloned().collect(), &git_dirs, cfg);
+    unreachable.retain(|name, _| {
+        let scopes = match repo_scopes.get(name) {
+            Some(scopes) => scopes,
+            None => return true,
+        };
+        let found = find_repos(&repos[name], scopes, &index);
+        if found.is_empty() {
+            return true;
+        }
+        let ranked = found.into_iter().map(|(full_path, score)| {
+            ResolveCandidate { full_path, score, strategy: ResolveStrategy::Git, action: ResolveAction::Update }
+        }).collect();
+        candidates.insert(name.to_owned(), ranked);
+        false
+    });
+    
+    // Select search & scope functions
+    let (search_fn, strategy) = match resolve_type {
+        ResolveType::Matching => (matching_file_names, ResolveStrategy::Matching),
+    };
+
+    let scope_fn: ScopeFn = if cfg.roots.is_empty() { in_parent_dir } else { in_search_roots };
+
+    debug!("Parameters");
+    debug!("\tresolve_type: {:?}", resolve_type);
+    debug!("\tcfg         : {:?}", cfg);
+
+    debug!("Attempting to search for files...");
+    let results: IndexMap<String, ScopeResults> = search_for(search_fn, scope_fn, &unreachable, cfg);
+    show_search_results(&results);
+
+    for (name, nested_entries) in results.into_iter() {
+        let previous = unreachable[&name].full_path.to_owned().unwrap();
+        let mut ranked: Vec<ResolveCandidate> = nested_entries.into_iter()
+            .flat_map(|(_, search_results)| search_results.into_iter())
+            .map(|file| {
+                let full_path = file.path().to_path_buf();
+                let score = score_candidate(&previous, &full_path);
+                ResolveCandidate { full_path, score, strategy, action: ResolveAction::Update }
+            }).collect();
+        if ranked.is_empty() {
+            continue;
+        }
+        // Nearer candidates first, keeping the search order for candidates of equal score
+        ranked.sort_by(|c1, c2| c2.score.total_cmp(&c1.score));
+        candidates.insert(name, ranked);
+    }
+    candidates
+}
+
+/** Find fixes for unreachable or broken paths
+  * 
   * Resolve Modes:
-  *     - Automode  : Selects the first/best possible candidate, 
-  *     - Manual    : Defer resolve choice to the user.
+  *     - Automode  : Selects the first/best possible candidate. Trashed shortpaths
+  *                   are skipped, since restoring or removing them is left to the user.
+  *     - Manual    : Defer resolve choice to the user, with an interactive picker
+  *                   or with line prompts when not run from a terminal.
+  *
+  * No changes are made to the shortpaths. The returned plan
+  * can be shown to the user, or applied with `apply_resolve_plan`.
   */
-pub fn resolve(shortpaths: &mut SP, resolve_type: ResolveType, mode: Mode, dry_run: bool) {
-    info!("resolve()");
+pub fn plan_resolve(shortpaths: &SP, repos: &Repos, resolve_type: ResolveType, mode: Mode, cfg: &ResolveConfig) -> ResolvePlan {
+    info!("plan_resolve()");
+    let mut plan = ResolvePlan::new();
 
     let unreachable = find_unreachable(shortpaths);
     if unreachable.is_empty() {
         debug!("None found");
-        println!("No unreachable paths found");
-        exit(0);
+        return plan;
     }
 
     // TODO: Create Display for Unreachable type
     show_unreachable(&unreachable);
-    
-    // Select search & scope functions
-    let search_fn = match resolve_type {
-        ResolveType::Matching => matching_file_names,
-    };
 
-    let scope_fn = in_parent_dir;
+    let candidates = find_candidates(&unreachable, repos, resolve_type, cfg);
+    if candidates.is_empty() {
+        debug!("No candidates found");
+        return plan;
+    }
 
-    debug!("Parameters");
-    debug!("\tresolve_type: {:?}", resolve_type);
     debug!("\tmode        : {:?}", mode);
-    debug!("\tdry_run     : {}", dry_run);
+    let mut use_picker = mode == Mode::Manual && stdin().is_terminal() && stderr().is_terminal();
+    let choices: IndexMap<String, ResolveCandidate> = match mode {
+        Mode::Automatic => candidates.into_iter()
+            .filter_map(|(name, ranked)| Some((name, ranked.into_iter().next()?)))
+            .filter(|(_, candidate)| candidate.strategy != ResolveStrategy::Trash)
+            .collect(),
+        Mode::Manual if use_picker => {
+            pick_candidates(&unreachable, &candidates).unwrap_or_else(|e| {
+                eprintln!("Could not run the resolve picker: {}", e);
+                use_picker = false;
+                prompt_candidates(&unreachable, &candidates)
+            })
+        }
+        Mode::Manual => prompt_candidates(&unreachable, &candidates),
+    };
 
-    debug!("Attempting to search for files...");
-    let results: IndexMap<String, ScopeResults> = search_for(search_fn, scope_fn, &unreachable);
+    for (name, candidate) in choices.into_iter() {
+        let sp = &unreachable[&name];
+        let updated = match candidate.action {
+            ResolveAction::Update => fold_shortpath_for(Some(&name), candidate.full_path.clone(), shortpaths, None),
+            _ => sp.path.to_owned(),
+        };
+        let entry = ResolveEntry {
+            name,
+            previous: sp.path.to_owned(),
+            updated,
+            full_path: candidate.full_path,
+            score: candidate.score,
+            strategy: candidate.strategy,
+            action: candidate.action,
+        };
+        debug!("Planned: {:?}", entry);
+        plan.push(entry);
+    }
 
-    // Exit early if no matches found
-    if results.is_empty() {
-        println!("No matches found.");
-        exit(0);
+    // Offer to repair the shortpaths under every moved shortpath
+    let descendants = find_descendants(shortpaths, &plan);
+    if descendants.is_empty() {
+        return plan;
     }
-    show_search_results(&results);
+    let accepted: Vec<ResolveEntry> = match mode {
+        Mode::Automatic if cfg.rewrite_descendants => descendants.into_values().flatten().collect(),
+        Mode::Automatic => {
+            let count: usize = descendants.values().map(Vec::len).sum();
+            eprintln!("Not rewriting {} shortpaths under moved shortpaths, pass --rewrite-descendants to rewrite them", count);
+            vec![]
+        }
+        Mode::Manual if use_picker => pick_descendants(&descendants).unwrap_or_else(|e| {
+            eprintln!("Could not run the resolve picker: {}", e);
+            prompt_descendants(shortpaths, descendants)
+        }),
+        Mode::Manual => prompt_descendants(shortpaths, descendants),
+    };
+    // Rewriting a descendant takes precedence over matching its file name
+    plan.entries.retain(|entry| {
+        entry.strategy != ResolveStrategy::Matching || !accepted.iter().any(|d| d.name == entry.name)
+    });
+    plan.entries.extend(accepted);
+    plan
+}
+
+/// Ask whether to rewrite the shortpaths under each moved shortpath
+fn prompt_descendants(shortpaths: &SP, descendants: IndexMap<String, Vec<ResolveEntry>>) -> Vec<ResolveEntry> {
+    let mut accepted = vec![];
+    for (parent, entries) in descendants.into_iter() {
+        let previous = shortpaths[&parent].full_path.to_owned().unwrap();
+        let message = format!("Rewrite {} shortpaths under {} to use ${}? [yes/no]: ",
+            entries.len(), previous.display(), parent);
+        let input = prompt_until_valid(&message, |input| matches!(input.trim_end(), "yes" | "no"));
+        match input {
+            Some(input) if input.trim_end() == "yes" => accepted.extend(entries),
+            Some(_) => {}
+            None => break,
+        }
+    }
+    accepted
+}
 
-    // Store the updates to make
-    let mut updates: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+/** Find the shortpaths under the previous locations of moved shortpaths
+  *
+  * When `proj` moves from `/old/proj` to `/new/proj`, a shortpath
+  * like `/old/proj/src` is rewritten to `$proj/src` so that it follows
+  * the fix, along with any future moves of `proj`.
+  * Shortpaths that already use an alias are left as is.
+  */
+pub fn find_descendants(shortpaths: &SP, plan: &ResolvePlan) -> IndexMap<String, Vec<ResolveEntry>> {
+    info!("find_descendants()");
+    // Prefer the deepest moved parent of each descendant
+    let mut moved: Vec<(&ResolveEntry, PathBuf)> = plan.entries.iter()
+        .filter(|entry| entry.action == ResolveAction::Update)
+        .filter_map(|entry| Some((entry, shortpaths.get(&entry.name)?.full_path.to_owned()?)))
+        .collect();
+    moved.sort_by_key(|(_, previous)| std::cmp::Reverse(previous.components().count()));
+
+    let mut descendants: IndexMap<String, Vec<ResolveEntry>> = IndexMap::new();
+    for (name, sp) in shortpaths.iter() {
+        let planned = plan.entries.iter().any(|entry| &entry.name == name && entry.strategy != ResolveStrategy::Matching);
+        let full_path = match &sp.full_path {
+            Some(full_path) if !planned => full_path,
+            _ => continue,
+        };
+        let parent = moved.iter().find(|(entry, previous)| {
+            &entry.name != name && full_path != previous && full_path.starts_with(previous)
+        });
+        let (parent, previous) = match parent {
+            Some(parent) => parent,
+            None => continue,
+        };
+        // Only literal paths are rewritten, since aliased paths follow their alias
+        if sp.path.to_string_lossy().starts_with('$') {
+            continue;
+        }
+        let alias = format!("${}", parent.name);
+        let relative = full_path.strip_prefix(previous).unwrap();
+        let entry = ResolveEntry {
+            name: name.to_owned(),
+            previous: sp.path.to_owned(),
+            updated: PathBuf::from(alias).join(relative),
+            full_path: parent.full_path.join(relative),
+            score: 1.0,
+            strategy: ResolveStrategy::Descendant,
+            action: ResolveAction::Update,
+        };
+        debug!("Descendant: {:?}", entry);
+        descendants.entry(parent.name.to_owned()).or_default().push(entry);
+    }
+    descendants
+}
 
-    for (name, sp) in unreachable.iter() {
-        let previous = sp.to_owned().full_path.unwrap();
-        for (_, nested_entries) in &results {
-            let choice = match mode {
-                Mode::Automatic => auto_resolve(name.to_owned(), nested_entries.to_owned()),
-                Mode::Manual => manual_resolve(name.to_owned(), &previous.clone(), nested_entries.to_owned()),
-            };
+/** Remove a shortpath, keeping the order of the others
+  *
+  * Shortpaths that depend on the removed shortpath have its path written in place of the alias,
+  * so they keep pointing to the same place.
+  */
+pub fn remove_and_inline(shortpaths: &mut SP, name: &str) -> Option<Shortpath> {
+    let removed = shortpaths.shift_remove(name)?;
+    for (dependent, sp) in shortpaths.iter_mut() {
+        if get_alias_dependency(sp).as_deref() != Some(name) {
+            continue;
+        }
+        let rest: PathBuf = sp.path.components().skip(1).collect();
+        let inlined = if rest.as_os_str().is_empty() { removed.path.to_owned() } else { removed.path.join(rest) };
+        eprintln!("Rewrote {}: {} to {}", dependent, sp.path.display(), inlined.display());
+        sp.path = inlined;
+    }
+    Some(removed)
+}
 
-            if let Some(updated) = choice {
-                updates.push((name.to_owned(), previous.clone(), updated.1));
+/** Apply the changes of a resolve plan to the shortpaths
+  *
+  * Entries whose shortpath was removed or changed since the plan was made
+  * are skipped, and returned entries are the ones that were applied.
+  */
+pub fn apply_resolve_plan(shortpaths: &mut SP, plan: &ResolvePlan) -> Vec<ResolveEntry> {
+    info!("apply_resolve_plan()");
+    let mut applied = vec![];
+    for entry in plan.entries.iter() {
+        match shortpaths.get(&entry.name) {
+            Some(sp) if sp.path == entry.previous => {
+                debug!("Name    : {}", entry.name);
+                debug!("Action  : {}", entry.action);
+                debug!("Updated : {}", entry.updated.display());
+                match &entry.action {
+                    ResolveAction::Update => {
+                        update_shortpath_path(&entry.name, entry.updated.to_owned(), Some(entry.full_path.to_owned()), shortpaths);
+                    }
+                    ResolveAction::Restore { trashed, info } => {
+                        if let Err(e) = restore_trashed(trashed, info, &entry.full_path) {
+                            eprintln!("Could not restore {} from the trash: {}", entry.name, e);
+                            continue;
+                        }
+                        update_shortpath_path(&entry.name, entry.updated.to_owned(), Some(entry.full_path.to_owned()), shortpaths);
+                    }
+                    ResolveAction::Remove => {
+                        remove_and_inline(shortpaths, &entry.name);
+                    }
+                }
+                applied.push(entry.to_owned());
             }
+            Some(_) => eprintln!("Skipping {}: shortpath was changed since the plan was made", entry.name),
+            None    => eprintln!("Skipping {}: shortpath does not exist", entry.name),
         }
     }
+    applied
+}
+
+/** Fix unreachable or broken paths
+  *
+  * Plans and applies the fixes. If `dry_run` is set, the plan is
+  * returned without making any changes.
+  */
+pub fn resolve(shortpaths: &mut SP, repos: &Repos, resolve_type: ResolveType, mode: Mode, cfg: &ResolveConfig, dry_run: bool) -> ResolvePlan {
+    info!("resolve()");
+    let plan = plan_resolve(shortpaths, repos, resolve_type, mode, cfg);
+    debug!("\tdry_run     : {}", dry_run);
+    if !dry_run {
+        apply_resolve_plan(shortpaths, &plan);
+    }
+    plan
+}
 
-    for (name, _, updated) in updates.into_iter() {
-        debug!("Name    : {name}");
-        debug!("Updated : {}", updated.display());
-        update_shortpath(shortpaths, &name, None, Some(updated))
+/// Display a resolve plan in the given output format
+pub fn show_resolve_plan(plan: &ResolvePlan, format: PlanFormat) {
+    match format {
+        PlanFormat::Table if plan.is_empty() => println!("No changes to make"),
+        PlanFormat::Table   => print!("{}", plan.to_table()),
+        PlanFormat::Json    => println!("{}", plan.to_json()),
     }
 }
 
-/** Serialize shortpaths to other formats for use in other applications */
-pub fn export_shortpaths(shortpaths: &SP, export_type: ExportType, output_file: Option<PathBuf>) -> PathBuf {
+/** Serialize shortpaths to other formats for use in other applications
+  *
+  * Exports are written to `output_file`, or to the exporter's user path when installing.
+  * Only exports with an install path can be installed, which the CLI checks beforehand.
+  * Returns an error if the export, or a file it updates, can't be read or written.
+  */
+pub fn export_shortpaths(shortpaths: &SP, export_type: ExportType, output_file: Option<PathBuf>, options: ExportOptions, install: bool) -> io::Result<PathBuf> {
     // Sets environment variables
     let mut evars = EnvVars::new();
     let vars = evars.vars.non_null().unique(shortpaths).strict();
     evars.vars = vars;
 
-    let exp = get_exporter(export_type);
+    let exp = get_exporter_with(export_type, options);
+    let output_file = match install {
+        true => Some(PathBuf::from(exp.get_install_path().expect("This export can't be installed"))),
+        false => output_file,
+    };
 
     let dest = exp.prepare_directory(output_file);
-    exp.write_completions(&dest, shortpaths.to_owned())
+    exp.write_export(&dest, shortpaths.to_owned())
 }
 
 pub fn update_shortpath_name(current_name: &str, new_name: String, shortpaths: &mut SP) {
@@ -553,12 +956,16 @@ pub fn update_shortpath_path(current_name: &str, new_path: PathBuf, full_path: O
 }
 
 /** Update a single shortpath's alias name or path
-  * Changes the name or path if given and are unique */
-pub fn update_shortpath(shortpaths: &mut SP, current_name: &str, name: Option<String>, path: Option<PathBuf>) {
-    let entry_exists = || { shortpaths.get(current_name).is_some() }; 
-    match (entry_exists(), name, path) {
-        (true, Some(new_name), _) => { update_shortpath_name(current_name, new_name, shortpaths); }
-        (true, _, Some(new_path)) => { update_shortpath_path(current_name, new_path.clone(), Some(new_path), shortpaths); }
+  * Changes the name or path if given and are unique.
+  * Only the shortpath and the shortpaths that depend on it are re-expanded. */
+pub fn update_shortpath(paths: &mut Shortpaths, current_name: &str, name: Option<String>, path: Option<PathBuf>) {
+    let entry_exists = paths.shortpaths.contains_key(current_name);
+    match (entry_exists, name, path) {
+        (true, Some(new_name), _) => {
+            let (removed, _) = paths.remove_path(current_name);
+            paths.update_path(&new_name, removed.unwrap().path);
+        }
+        (true, _, Some(new_path)) => { paths.update_path(current_name, new_path); }
         (_, _, _)              => { println!("Nothing to do");}
     }
 }
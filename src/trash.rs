@@ -0,0 +1,122 @@
+use std::{
+    ffi::OsString,
+    fs::{create_dir_all, read_dir, read_to_string, remove_file, rename},
+    io,
+    os::unix::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use log::{debug, info};
+
+// Constant Strings
+pub const TRASH_INFO_EXT: &str      = "trashinfo";
+pub const TRASH_INFO_HEADER: &str   = "[Trash Info]";
+pub const TRASH_DATE_FORMAT: &str   = "%Y-%m-%dT%H:%M:%S";
+
+/** A file or directory sent to the XDG trash
+  *
+  * See the FreeDesktop.org Trash specification for more information.
+  */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashEntry {
+    /// Where the file was before it was trashed
+    pub original: PathBuf,
+    /// Where the file is now inside the trash
+    pub trashed: PathBuf,
+    /// The `.trashinfo` file that describes the trashed file
+    pub info: PathBuf,
+    pub deleted: Option<NaiveDateTime>,
+}
+
+impl TrashEntry {
+    /// Format the deletion date for display
+    pub fn deleted_on(&self) -> String {
+        match self.deleted {
+            Some(date) => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => String::from("an unknown date"),
+        }
+    }
+}
+
+/// Get the user's home trash directory
+pub fn trash_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|data_dir| data_dir.join("Trash"))
+}
+
+/// Decode the percent encoded paths of `.trashinfo` files
+pub fn percent_decode(s: &str) -> PathBuf {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => { decoded.push(byte); i += 3; }
+            (byte, _)          => { decoded.push(byte); i += 1; }
+        }
+    }
+    PathBuf::from(OsString::from_vec(decoded))
+}
+
+/// Parse the original path and deletion date of a `.trashinfo` file
+pub fn parse_trash_info(conts: &str) -> Option<(PathBuf, Option<NaiveDateTime>)> {
+    let mut lines = conts.lines().map(str::trim);
+    if lines.next()? != TRASH_INFO_HEADER {
+        return None;
+    }
+
+    let (mut path, mut deleted) = (None, None);
+    for line in lines {
+        match line.split_once('=') {
+            Some(("Path", value))           => path = Some(percent_decode(value)),
+            Some(("DeletionDate", value))   => deleted = NaiveDateTime::parse_from_str(value, TRASH_DATE_FORMAT).ok(),
+            _ => {}
+        }
+    }
+    // Only absolute paths are used in the home trash
+    path.filter(|path| path.is_absolute()).map(|path| (path, deleted))
+}
+
+/// Read every entry of a trash directory that still has its trashed file
+pub fn read_trash(trash: &Path) -> Vec<TrashEntry> {
+    info!("read_trash()");
+    let infos = match read_dir(trash.join("info")) {
+        Ok(infos) => infos,
+        Err(_) => return vec![],
+    };
+
+    infos.filter_map(Result::ok)
+        .map(|file| file.path())
+        .filter(|info| info.extension().is_some_and(|ext| ext == TRASH_INFO_EXT))
+        .filter_map(|info| read_trash_info(trash, &info))
+        .filter(|entry| entry.trashed.exists())
+        .collect()
+}
+
+/// Read the trash entry described by a `.trashinfo` file of a trash directory
+pub fn read_trash_info(trash: &Path, info: &Path) -> Option<TrashEntry> {
+    let (original, deleted) = parse_trash_info(&read_to_string(info).ok()?)?;
+    let trashed = trash.join("files").join(info.file_stem()?);
+    debug!("Trashed: {} -> {}", original.display(), trashed.display());
+    Some(TrashEntry { original, trashed, info: info.to_path_buf(), deleted })
+}
+
+/// Find the most recently trashed entry for a path
+pub fn find_trashed<'a>(path: &Path, entries: &'a [TrashEntry]) -> Option<&'a TrashEntry> {
+    entries.iter()
+        .filter(|entry| entry.original == path)
+        .max_by_key(|entry| entry.deleted)
+}
+
+/// Move a trashed file back to its original path, and remove its `.trashinfo` file
+pub fn restore_trashed(trashed: &Path, info: &Path, original: &Path) -> io::Result<()> {
+    if original.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", original.display())));
+    }
+    if let Some(parent) = original.parent() {
+        create_dir_all(parent)?;
+    }
+    rename(trashed, original)?;
+    remove_file(info)
+}
@@ -0,0 +1,115 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::ShortpathsBuilder,
+    check::{FindingKind, Severity, check_shortpaths},
+    resolve::ResolveConfig,
+    shortpaths::Shortpath,
+};
+
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all, write},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use indexmap::indexmap;
+
+#[test]
+fn test_check_aliases() {
+    enable_logging();
+    let sp = indexmap! {
+        "a".to_owned() => Shortpath::new(PathBuf::from("$b/a"), None),
+        "b".to_owned() => Shortpath::new(PathBuf::from("$a/b"), None),
+        "c".to_owned() => Shortpath::new(PathBuf::from("$missing/c"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let report = check_shortpaths(&shortpaths, &ResolveConfig::default());
+    let kinds: Vec<(&str, FindingKind)> = report.findings.iter()
+        .map(|finding| (finding.name.as_str(), finding.kind))
+        .collect();
+    assert_eq!(kinds, vec![("b", FindingKind::Cycle), ("a", FindingKind::Cycle), ("c", FindingKind::UndefinedAlias)],
+        "Broken aliases are reported instead of expanded");
+    assert_eq!(report.max_severity(), Some(Severity::Error));
+    assert_eq!(report.exit_code(), 2);
+}
+
+#[test]
+fn test_check_paths() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-check");
+    let _ = remove_dir_all(&root);
+    create_dir_all(root.join("dir")).unwrap();
+    write(root.join("file"), "").unwrap();
+    symlink(root.join("gone"), root.join("link")).unwrap();
+
+    let sp = indexmap! {
+        "dir".to_owned() => Shortpath::new(root.join("dir"), None),
+        "same".to_owned() => Shortpath::new(root.join("dir"), None),
+        "file".to_owned() => Shortpath::new(root.join("file"), None),
+        "nested".to_owned() => Shortpath::new(PathBuf::from("$file/nested"), None),
+        "link".to_owned() => Shortpath::new(root.join("link"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let report = check_shortpaths(&shortpaths, &ResolveConfig::default());
+    let kinds: Vec<(&str, FindingKind)> = report.findings.iter()
+        .map(|finding| (finding.name.as_str(), finding.kind))
+        .collect();
+    assert_eq!(kinds, vec![
+        ("file", FindingKind::TypeMismatch),
+        ("nested", FindingKind::Unreachable),
+        ("link", FindingKind::DanglingSymlink),
+        ("same", FindingKind::DuplicateTarget),
+    ]);
+    assert_eq!(report.findings[3].severity, Severity::Info, "Duplicate targets are allowed");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_check_report_formats() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-check-formats");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+
+    let sp = indexmap! {
+        "root".to_owned() => Shortpath::new(root.clone(), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+    let report = check_shortpaths(&shortpaths, &ResolveConfig::default());
+    assert!(report.findings.is_empty());
+    assert_eq!(report.exit_code(), 0);
+
+    remove_dir_all(&root).unwrap();
+    let report = check_shortpaths(&shortpaths, &ResolveConfig::default());
+    assert!(report.to_text().starts_with("error: root shortpath is unreachable"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["findings"][0]["kind"], "unreachable");
+    assert_eq!(json["findings"][0]["severity"], "error");
+
+    let sarif: serde_json::Value = serde_json::from_str(&report.to_sarif(None)).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"][0]["id"], "unreachable");
+    assert_eq!(sarif["runs"][0]["results"][0]["level"], "error");
+    assert_eq!(sarif["runs"][0]["results"][0]["locations"][0]["logicalLocations"][0]["name"], "root");
+
+    let sarif: serde_json::Value = serde_json::from_str(&report.to_sarif(Some(Path::new("/home/me/my config/shortpaths.toml")))).unwrap();
+    assert_eq!(sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "file:///home/me/my%20config/shortpaths.toml", "Config paths are percent encoded");
+}
+
+#[test]
+fn test_finding_kind_display() {
+    enable_logging();
+    let kinds = [
+        FindingKind::Unreachable, FindingKind::UndefinedAlias, FindingKind::Cycle, FindingKind::TypeMismatch,
+        FindingKind::PermissionDenied, FindingKind::DanglingSymlink, FindingKind::DuplicateTarget,
+    ];
+    for kind in kinds {
+        assert_eq!(serde_json::to_value(kind).unwrap(), kind.to_string(), "Finding kinds are shown like in JSON reports");
+    }
+}
@@ -0,0 +1,89 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::{Shortpaths, ShortpathsAlignExt, ShortpathsBuilder},
+    escape::{decode_path, encode_path},
+    export::{ShellExporter, bash::BashExporter, powershell::PowershellExporter},
+    shortpaths::{Shortpath, FindKeyIndexMapExt, fold_shortpath},
+};
+
+use std::{
+    ffi::OsString,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::PathBuf,
+};
+
+use indexmap::indexmap;
+use proptest::prelude::*;
+
+/// A path with a file name that is not valid UTF-8
+fn non_utf8_path(base: &str) -> PathBuf {
+    let mut bytes = base.as_bytes().to_vec();
+    bytes.extend_from_slice(b"/caf\xE9");
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[test]
+fn test_escape_paths() {
+    enable_logging();
+    let path = non_utf8_path("/home/user");
+    assert_eq!(encode_path(&path), "/home/user/caf\\xE9");
+    assert_eq!(decode_path("/home/user/caf\\xE9"), path);
+
+    assert_eq!(encode_path(&PathBuf::from("/home/user/docs")), "/home/user/docs", "Valid UTF-8 paths are unchanged");
+    assert_eq!(encode_path(&PathBuf::from("/a\\xE9")), "/a\\\\xE9", "Backslashes are escaped");
+    assert_eq!(decode_path("/a\\\\xE9"), PathBuf::from("/a\\xE9"));
+    assert_eq!(decode_path("/a\\b"), PathBuf::from("/a\\b"), "Backslashes that don't start an escape are kept");
+}
+
+proptest! {
+    #[test]
+    fn test_escape_roundtrip(bytes in prop::collection::vec(any::<u8>(), 0..32)) {
+        let path = PathBuf::from(OsString::from_vec(bytes));
+        let decoded = decode_path(&encode_path(&path));
+        prop_assert_eq!(decoded.as_os_str().as_bytes(), path.as_os_str().as_bytes());
+    }
+}
+
+#[test]
+fn test_escape_non_utf8_shortpaths() {
+    enable_logging();
+    let sp = indexmap! {
+        "base".to_owned() => Shortpath::new(PathBuf::from("/home/user"), None),
+        "cafe".to_owned() => Shortpath::new(non_utf8_path("$base"), None),
+    };
+    let paths = ShortpathsBuilder::from(sp).build().unwrap();
+    let shortpaths = paths.shortpaths.to_owned();
+    assert_eq!(shortpaths["cafe"].full_path, Some(non_utf8_path("/home/user")));
+    assert_eq!(fold_shortpath(non_utf8_path("/home/user"), &shortpaths), non_utf8_path("$base"));
+    assert_eq!(shortpaths.find_key_for_value(non_utf8_path("$base")), Some(&"cafe".to_owned()));
+
+    // Config files
+    let toml = paths.tab_align_paths();
+    assert!(toml.contains(r#"cafe = ['$base/caf\xE9']"#), "Invalid bytes are escaped in the config: {}", toml);
+    let read: Shortpaths = toml::from_str(&toml).unwrap();
+    assert_eq!(read.shortpaths["cafe"].path, non_utf8_path("$base"));
+
+    // Exports
+    let bash = BashExporter.gen_completions(shortpaths.to_owned());
+    assert!(bash.contains("export cafe=\"$base/caf\"$'\\xE9'\n"), "{}", bash);
+    let powershell = PowershellExporter.gen_completions(shortpaths);
+    assert!(powershell.contains("# Skipped cafe: $base/caf\\xE9 is not valid UTF-8\n"), "{}", powershell);
+}
+
+#[test]
+fn test_legacy_config_backslashes() {
+    enable_logging();
+    // Written before paths were escaped
+    let legacy = r#"[shortpaths]
+share = 'C:\\share'
+raw   = '/tmp/\xAB\\b'
+"#;
+    let read: Shortpaths = toml::from_str(legacy).unwrap();
+    assert_eq!(read.shortpaths["share"].path, PathBuf::from(r"C:\\share"), "Backslashes in older configs are kept as is");
+    assert_eq!(read.shortpaths["raw"].path, PathBuf::from(r"/tmp/\xAB\\b"));
+
+    let paths = Shortpaths { shortpaths: read.shortpaths, ..Default::default() };
+    let saved = paths.tab_align_paths();
+    assert_eq!(saved, legacy, "Older configs are saved back unchanged");
+}
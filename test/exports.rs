@@ -7,31 +7,624 @@ use crate::helpers::{
     setup_shortpaths,
 };
 
-use shortpaths::export::{Export, bash::BashExporter};
+use shortpath::{
+    escape::file_uri,
+    export::{
+        Export, ShellExporter, ExportOptions, get_exporter_with, update_marked_block,
+        bash::{self, BashExporter, BASH_JUMP_FUNCTION},
+        direnv::DirenvExporter,
+        dotenv::{DotenvExporter, quote_value},
+        environment_d::EnvironmentDExporter,
+        pam::{self, PamExporter},
+        fish::{FishExporter, quote_path},
+        gtk::{GtkBookmarksExporter, sync_bookmarks},
+        json::JsonExporter,
+        nushell::NushellExporter,
+        powershell::{self, PowershellExporter, POWERSHELL_COMPLETER, POWERSHELL_HOOKS},
+        user_dirs::{UserDirsExporter, record_user_dirs, update_user_dirs, user_dir_entries},
+        zsh::ZshExporter,
+    },
+    app::ExportType,
+    shortpaths::{Shortpath, SP},
+};
+
+use std::{
+    env::temp_dir,
+    ffi::OsStr,
+    fs::{remove_dir_all, create_dir_all, read_to_string, write, metadata, set_permissions, Permissions},
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use indexmap::indexmap;
 
 #[test]
 fn test_serialize_bash() {
     enable_logging();
     let shortpaths = setup_shortpaths(shortpaths_default);
 
-    let exp = BashExporter::default()
-        .set_shortpaths(&shortpaths);
+    let exp = BashExporter;
 
     // Test
-    let actual = exp.gen_completions();
+    let actual = exp.gen_completions(shortpaths);
     let expect = "#!/bin/bash\n\nexport a=\"aaaa\"\nexport b=\"$a/bbbb\"\nexport c=\"$b/cccc\"\nexport d=\"$a/dddd\"\n";
+    let expect = format!("{}\n_SHORTPATH_ALIASES=(a b c d)\n{}", expect, BASH_JUMP_FUNCTION);
     assert_eq!(actual, expect, "Bash shell completions are generated in the correct order");
+    assert_eq!(exp.get_install_path(), Some(exp.get_completions_user_path()), "Bash exports are installed to the user completions path");
 }
 
 #[test]
 fn test_nested_serialize_bash() {
     enable_logging();
     let shortpaths = setup_shortpaths(shortpaths_nested);
-    let exp = BashExporter::default()
-        .set_shortpaths(&shortpaths);
+    let exp = BashExporter;
 
     // Test
-    let actual = exp.gen_completions();
+    let actual = exp.gen_completions(shortpaths);
     let expect = "#!/bin/bash\n\nexport a=\"aaaa\"\nexport b=\"$a/bbbb\"\nexport c=\"$b/cccc\"\nexport d=\"$c/dddd\"\n";
+    let expect = format!("{}\n_SHORTPATH_ALIASES=(a b c d)\n{}", expect, BASH_JUMP_FUNCTION);
     assert_eq!(actual, expect, "Bash shell completions are generated in the correct order");
 }
+
+#[test]
+fn test_nested_serialize_zsh() {
+    enable_logging();
+    let shortpaths = setup_shortpaths(shortpaths_nested);
+    let exp = ZshExporter;
+
+    // Test
+    let actual = exp.gen_completions(shortpaths);
+    let expect = concat!(
+        "#!/bin/zsh\n\n",
+        "export a=\"aaaa\"\nhash -d a=\"$a\"\n",
+        "export b=\"$a/bbbb\"\nhash -d b=\"$b\"\n",
+        "export c=\"$b/cccc\"\nhash -d c=\"$c\"\n",
+        "export d=\"$c/dddd\"\nhash -d d=\"$d\"\n",
+    );
+    assert_eq!(actual, expect, "Zsh aliases are also registered as named directories");
+    assert!(exp.get_install_path().unwrap().ends_with("/zsh/site-functions/shortpath_aliases"), "Zsh exports are installed to $fpath");
+}
+
+#[test]
+fn test_nested_serialize_fish() {
+    enable_logging();
+    let shortpaths = setup_shortpaths(shortpaths_nested);
+    let exp = FishExporter;
+
+    // Test
+    let actual = exp.gen_completions(shortpaths);
+    let expect = "#!/usr/bin/env fish\n\nset -gx a \"aaaa\"\nset -gx b \"$a/bbbb\"\nset -gx c \"$b/cccc\"\nset -gx d \"$c/dddd\"\n";
+    assert_eq!(actual, expect, "Fish variables are generated in the correct order");
+}
+
+#[test]
+fn test_fish_dependency_order() {
+    enable_logging();
+    let shortpaths: SP = indexmap!{
+        "d".to_owned() => Shortpath::new(PathBuf::from("$c/dddd"), None),
+        "c".to_owned() => Shortpath::new(PathBuf::from("${env:HOME}/cccc"), None),
+        "e".to_owned() => Shortpath::new(PathBuf::from("/eeee"), None),
+    };
+    let actual = FishExporter.gen_completions(shortpaths);
+    let expect = "#!/usr/bin/env fish\n\nset -gx c \"$HOME/cccc\"\nset -gx d \"$c/dddd\"\nset -gx e \"/eeee\"\n";
+    assert_eq!(actual, expect, "Aliases are set before the variables that depend on them");
+}
+
+#[test]
+fn test_fish_quoting() {
+    enable_logging();
+    assert_eq!(quote_path(Path::new("")), "\"\"");
+    assert_eq!(quote_path(Path::new("/a b/$c/\"d\"")), "\"/a b/\\$c/\\\"d\\\"\"", "Literal $ and quotes are escaped");
+    assert_eq!(quote_path(Path::new("$a/$b")), "\"$a/\\$b\"", "Only the leading alias is expanded");
+    assert_eq!(quote_path(Path::new("$a-b/c")), "\"\\$a-b/c\"", "Components that aren't fish variables are escaped");
+
+    let path = Path::new(OsStr::from_bytes(b"/tmp/\xFFx"));
+    assert_eq!(quote_path(path), "\"/tmp/\"\\XFF\"x\"", "Bytes that are not valid UTF-8 are written as raw bytes");
+}
+
+#[test]
+fn test_nested_serialize_nushell() {
+    enable_logging();
+    let shortpaths = setup_shortpaths(shortpaths_nested);
+    let exp = NushellExporter::default();
+
+    // Test
+    let actual = exp.gen_completions(shortpaths);
+    let expect = concat!(
+        "export-env {\n",
+        "    $env.a = \"aaaa\"\n",
+        "    $env.b = $\"($env.a)/bbbb\"\n",
+        "    $env.c = $\"($env.b)/cccc\"\n",
+        "    $env.d = $\"($env.c)/dddd\"\n",
+        "}\n",
+    );
+    assert_eq!(actual, expect, "Nushell modules set the variables in the correct order");
+}
+
+#[test]
+fn test_serialize_nushell_jump_commands() {
+    enable_logging();
+    let shortpaths: SP = indexmap!{
+        "a".to_owned() => Shortpath::new(PathBuf::from("/a (1)/\"b\""), None),
+        "c".to_owned() => Shortpath::new(PathBuf::from("$a/(c)"), None),
+    };
+    let exp = NushellExporter { jump_commands: true };
+
+    // Test
+    let actual = exp.gen_completions(shortpaths);
+    let expect = concat!(
+        "export-env {\n",
+        "    $env.a = \"/a (1)/\\\"b\\\"\"\n",
+        "    $env.c = $\"($env.a)/\\(c)\"\n",
+        "}\n",
+        "\nexport def --env a [] { cd $env.a }\n",
+        "\nexport def --env c [] { cd $env.c }\n",
+    );
+    assert_eq!(actual, expect, "Nushell strings are escaped, and every shortpath gets a jump command");
+}
+
+#[test]
+fn test_nushell_jump_commands_skip_builtins() {
+    enable_logging();
+    let shortpaths: SP = indexmap!{
+        "open".to_owned() => Shortpath::new(PathBuf::from("/open"), None),
+        "proj".to_owned() => Shortpath::new(PathBuf::from("/proj"), None),
+    };
+    let exp = NushellExporter { jump_commands: true };
+
+    let actual = exp.gen_completions(shortpaths);
+    assert!(actual.contains("    $env.open = \"/open\"\n"), "Builtin names are still exported as variables");
+    assert!(actual.contains("\n# Skipped jump command for open: it would shadow the nushell command open\n"));
+    assert!(!actual.contains("export def --env open"), "Jump commands never shadow nushell builtins");
+    assert!(actual.contains("\nexport def --env proj [] { cd $env.proj }\n"));
+}
+
+/// Paths that run commands when they are written to a shell script unquoted
+const HOSTILE_PATHS: &[&str] = &[
+    "/tmp/\"; touch pwned; \"",
+    "/tmp/$(touch pwned)",
+    "/tmp/`touch pwned`",
+    "/tmp/${HOME}/$HOME",
+    "/tmp/back\\slash\\",
+    "/tmp/it's",
+    "/tmp/new\nline",
+    "/tmp/\u{201C}; touch pwned; \u{201D}",
+];
+
+#[test]
+fn test_bash_hostile_paths() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-hostile");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+
+    for (i, hostile) in HOSTILE_PATHS.iter().enumerate() {
+        let shortpaths: SP = indexmap!{
+            "a".to_owned() => Shortpath::new(PathBuf::from(hostile), None),
+            "b".to_owned() => Shortpath::new(PathBuf::from("$a/$(touch pwned)"), None),
+        };
+        let script = root.join(format!("{}.bash", i));
+        write(&script, BashExporter.gen_completions(shortpaths)).unwrap();
+
+        let output = Command::new("bash")
+            .current_dir(&root)
+            .arg("-c").arg("source \"$0\" && printf '%s\\0%s' \"$a\" \"$b\"").arg(&script)
+            .output().unwrap();
+        let actual = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(actual, format!("{}\0{}/$(touch pwned)", hostile, hostile), "Bash sources hostile paths literally");
+        assert!(!root.join("pwned").exists(), "Sourcing {:?} does not run commands", hostile);
+    }
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_shell_quoting_hostile_paths() {
+    enable_logging();
+    let quote_bash = |path: &str| bash::quote_path(Path::new(path));
+    assert_eq!(quote_bash("$a/$(x)"), "\"$a/\\$(x)\"", "Only the leading alias is expanded in bash");
+    assert_eq!(quote_bash("/`x`\\\""), "\"/\\`x\\`\\\\\\\"\"");
+    assert_eq!(quote_bash("$a-b/c"), "\"\\$a-b/c\"", "References that aren't identifiers are escaped");
+
+    assert_eq!(powershell::quote_path("$a/$(x)"), "\"${Env:a}/`$(x)\"", "Aliases refer to the exported variables");
+    assert_eq!(powershell::quote_path("/`x`\"\u{201C}"), "\"/``x```\"`\u{201C}\"");
+    assert_eq!(quote_path(Path::new("/(x)/`x`")), "\"/(x)/`x`\"", "Fish only expands variables in double quotes");
+
+    let exp = NushellExporter::default();
+    assert_eq!(exp.format_alias("a", Path::new("$b/(x)\\\"")), "    $env.a = $\"($env.b)/\\(x)\\\\\\\"\"\n");
+}
+
+#[test]
+fn test_exports_skip_invalid_names() {
+    enable_logging();
+    let shortpaths: SP = indexmap!{
+        "a;touch pwned".to_owned() => Shortpath::new(PathBuf::from("/a"), None),
+        "b".to_owned() => Shortpath::new(PathBuf::from("/b"), None),
+    };
+    let expect = format!("#!/bin/bash\n\nexport b=\"/b\"\n\n_SHORTPATH_ALIASES=(b)\n{}", BASH_JUMP_FUNCTION);
+    assert_eq!(BashExporter.gen_completions(shortpaths.clone()), expect, "Names that can't be exported are skipped");
+    let expect = format!("$Env:b = \"/b\"\n\n{}\n$ShortpathAliases = @('b')\n{}", POWERSHELL_HOOKS, POWERSHELL_COMPLETER);
+    assert_eq!(PowershellExporter.gen_completions(shortpaths), expect);
+}
+
+#[test]
+fn test_bash_jump_function() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-jump");
+    let _ = remove_dir_all(&root);
+    create_dir_all(root.join("proj/src/bin")).unwrap();
+    create_dir_all(root.join("proj/sub dir")).unwrap();
+
+    let shortpaths: SP = indexmap!{
+        "proj".to_owned() => Shortpath::new(root.join("proj"), None),
+    };
+    let script = root.join("shortpath.bash");
+    write(&script, BashExporter.gen_completions(shortpaths)).unwrap();
+
+    let run = |commands: &str| -> String {
+        let output = Command::new("bash").arg("-c").arg(format!("source \"$0\" && {}", commands)).arg(&script).output().unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let complete = |word: &str| run(&format!("COMP_WORDS=(sp '{}'); COMP_CWORD=1; _sp; printf '%s\\n' \"${{COMPREPLY[@]}}\" | sort", word));
+
+    assert_eq!(run("sp proj/src && pwd"), format!("{}\n", root.join("proj/src").display()), "sp jumps to directories under shortpaths");
+    assert_eq!(run("sp PATH || echo refused"), "refused\n", "sp only jumps to shortpaths");
+    assert_eq!(complete("pr"), "proj/\n", "Shortpath names are completed");
+    assert_eq!(complete("proj/"), "proj/src/\nproj/sub dir/\n", "Directories under shortpaths are completed");
+    assert_eq!(complete("proj/src/"), "proj/src/bin/\n");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_nested_serialize_powershell() {
+    enable_logging();
+    let shortpaths = setup_shortpaths(shortpaths_nested);
+    let exp = PowershellExporter;
+
+    // Test
+    let actual = exp.gen_completions(shortpaths);
+    let expect = include_str!("snapshots/powershell_profile.ps1");
+    assert_eq!(actual, expect, "PowerShell variables are set in dependency order, followed by the hooks and completer");
+}
+
+/// Shortpaths with expanded paths, for the data formats
+fn shortpaths_expanded() -> SP {
+    indexmap!{
+        "a".to_owned() => Shortpath::new(PathBuf::from("/aaaa"), Some(PathBuf::from("/aaaa"))),
+        "b".to_owned() => Shortpath::new(PathBuf::from("$a/it's"), Some(PathBuf::from("/aaaa/it's"))),
+        "c".to_owned() => Shortpath::new(PathBuf::from("$z/cccc"), None),
+    }
+}
+
+#[test]
+fn test_serialize_json() {
+    enable_logging();
+    let actual = JsonExporter::default().gen_export(shortpaths_expanded());
+    let expect = r#"{
+  "shortpaths": {
+    "a": {
+      "path": "/aaaa",
+      "full_path": "/aaaa"
+    },
+    "b": {
+      "path": "$a/it's",
+      "full_path": "/aaaa/it's"
+    },
+    "c": {
+      "path": "$z/cccc",
+      "full_path": null
+    }
+  }
+}
+"#;
+    assert_eq!(actual, expect, "JSON exports have the raw and expanded paths");
+
+    let actual: serde_json::Value = serde_json::from_str(&JsonExporter { metadata: true }.gen_export(shortpaths_expanded())).unwrap();
+    assert_eq!(actual["metadata"]["generator"], "shortpath", "Metadata is included when requested");
+    assert_eq!(actual["shortpaths"]["a"]["full_path"], "/aaaa");
+
+    let mut shortpaths = shortpaths_expanded();
+    shortpaths.insert(String::new(), Shortpath::new(PathBuf::from("/empty"), None));
+    let actual: serde_json::Value = serde_json::from_str(&JsonExporter::default().gen_export(shortpaths)).unwrap();
+    assert!(actual["shortpaths"].get("").is_none(), "Invalid names are skipped");
+    assert_eq!(actual["shortpaths"].as_object().unwrap().len(), 3);
+
+    let shortpaths: SP = indexmap!{
+        "win".to_owned() => Shortpath::new(PathBuf::from("/a\\b"), Some(PathBuf::from("/a\\b"))),
+        "cafe".to_owned() => Shortpath::new(PathBuf::from(OsStr::from_bytes(b"/caf\xE9")), None),
+    };
+    let actual: serde_json::Value = serde_json::from_str(&JsonExporter::default().gen_export(shortpaths)).unwrap();
+    assert_eq!(actual["shortpaths"]["win"]["path"], "/a\\b", "UTF-8 paths are exported as is");
+    assert_eq!(actual["shortpaths"]["win"]["full_path"], "/a\\b");
+    assert_eq!(actual["shortpaths"]["cafe"]["path"], serde_json::json!(["/caf\\xE9"]), "Other paths are escaped like in the config");
+}
+
+#[test]
+fn test_serialize_yaml() {
+    enable_logging();
+    let exp = get_exporter_with(ExportType::Yaml, ExportOptions::default());
+    let actual = exp.gen_export(shortpaths_expanded());
+    let expect = concat!(
+        "shortpaths:\n",
+        "  a:\n    path: /aaaa\n    full_path: /aaaa\n",
+        "  b:\n    path: $a/it's\n    full_path: /aaaa/it's\n",
+        "  c:\n    path: $z/cccc\n    full_path: null\n",
+    );
+    assert_eq!(actual, expect, "YAML exports have the raw and expanded paths");
+
+    let exp = get_exporter_with(ExportType::Yaml, ExportOptions { metadata: true, ..Default::default() });
+    assert!(exp.gen_export(shortpaths_expanded()).starts_with("metadata:\n  generator: shortpath\n"));
+}
+
+#[test]
+fn test_serialize_dotenv() {
+    enable_logging();
+    let actual = DotenvExporter::default().gen_export(shortpaths_expanded());
+    let expect = concat!(
+        "# a: /aaaa\na='/aaaa'\n",
+        "# b: $a/it's\nb=\"/aaaa/it's\"\n",
+        "# c: $z/cccc\n# Skipped c: the path could not be expanded\n",
+    );
+    assert_eq!(actual, expect, "Dotenv exports have the expanded paths, with the raw paths as comments");
+
+    assert_eq!(quote_value("/$HOME/a\nb\\'"), "\"/\\$HOME/a\\nb\\\\'\"", "Double quoted values are escaped");
+    assert!(DotenvExporter { metadata: true }.gen_export(SP::new()).starts_with("# Generated by shortpath"));
+}
+
+/// Shortpaths that depend on environment variables, or can't be expanded to absolute paths
+fn shortpaths_session() -> SP {
+    indexmap!{
+        "home".to_owned() => Shortpath::new(PathBuf::from("${env:HOME}/x"), Some(PathBuf::from("/home/user/x"))),
+        "proj".to_owned() => Shortpath::new(PathBuf::from("${env:PROJECT_ROOT}/proj"), Some(PathBuf::from("/src/proj"))),
+        "src".to_owned() => Shortpath::new(PathBuf::from("$proj/$src @x"), Some(PathBuf::from("/src/proj/$src @x"))),
+        "rel".to_owned() => Shortpath::new(PathBuf::from("rel"), Some(PathBuf::from("rel"))),
+        "quote".to_owned() => Shortpath::new(PathBuf::from("/a\"b"), Some(PathBuf::from("/a\"b"))),
+        "broken".to_owned() => Shortpath::new(PathBuf::from("$missing/x"), None),
+    }
+}
+
+#[test]
+fn test_serialize_environment_d() {
+    enable_logging();
+    let actual = EnvironmentDExporter.gen_export(shortpaths_session());
+    let expect = concat!(
+        "home=\"/home/user/x\"\n",
+        "# Warning: proj depends on $PROJECT_ROOT, which can't be resolved here. Its current value was exported.\n",
+        "proj=\"/src/proj\"\n",
+        "# Warning: src depends on $PROJECT_ROOT, which can't be resolved here. Its current value was exported.\n",
+        "src=\"/src/proj/\\$src @x\"\n",
+        "# Skipped rel: rel is not an absolute path\n",
+        "quote=\"/a\\\"b\"\n",
+        "# Skipped broken: the path could not be expanded\n",
+    );
+    assert_eq!(actual, expect, "environment.d has the expanded absolute paths");
+}
+
+#[test]
+fn test_serialize_pam() {
+    enable_logging();
+    let actual = PamExporter.gen_export(shortpaths_session());
+    let expect = concat!(
+        "# >>> shortpath >>>\n",
+        "home DEFAULT=\"/home/user/x\"\n",
+        "# Warning: proj depends on $PROJECT_ROOT, which can't be resolved here. Its current value was exported.\n",
+        "proj DEFAULT=\"/src/proj\"\n",
+        "# Warning: src depends on $PROJECT_ROOT, which can't be resolved here. Its current value was exported.\n",
+        "src DEFAULT=\"/src/proj/\\$src \\@x\"\n",
+        "# Skipped rel: rel is not an absolute path\n",
+        "# Skipped quote: /a\"b can't be written to this format\n",
+        "# Skipped broken: the path could not be expanded\n",
+        "# <<< shortpath <<<\n",
+    );
+    assert_eq!(actual, expect, "pam_environment has the expanded absolute paths");
+    assert_eq!(pam::quote_value("/a\\b"), Some(String::from("\"/a\\\\b\"")));
+}
+
+#[test]
+fn test_pam_keeps_user_variables() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-pam");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let pam_environment = root.join(".pam_environment");
+    write(&pam_environment, "EDITOR DEFAULT=vim\n").unwrap();
+
+    let shortpaths: SP = indexmap!{
+        "a".to_owned() => Shortpath::new(PathBuf::from("/a"), Some(PathBuf::from("/a"))),
+    };
+    PamExporter.write_export(&pam_environment, shortpaths).unwrap();
+    let shortpaths: SP = indexmap!{
+        "b".to_owned() => Shortpath::new(PathBuf::from("/b"), Some(PathBuf::from("/b"))),
+    };
+    PamExporter.write_export(&pam_environment, shortpaths).unwrap();
+    assert_eq!(read_to_string(&pam_environment).unwrap(), "EDITOR DEFAULT=vim\n\n# >>> shortpath >>>\nb DEFAULT=\"/b\"\n# <<< shortpath <<<\n",
+        "Only the shortpath block is replaced");
+
+    write(&pam_environment, b"LANG DEFAULT=\xff\n").unwrap();
+    assert!(PamExporter.write_export(&pam_environment, SP::new()).is_err(), "Unreadable files are an error");
+    assert_eq!(std::fs::read(&pam_environment).unwrap(), b"LANG DEFAULT=\xff\n", "Unreadable files are left untouched");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_export_fileperms() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-fileperms");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let mode = |path: &Path| metadata(path).unwrap().permissions().mode() & 0o777;
+
+    let script = root.join("shortpath.bash");
+    BashExporter.write_export(&script, shortpaths_default()).unwrap();
+    assert_eq!(mode(&script), 0o744, "New shell scripts are made executable");
+
+    let json = root.join("shortpaths.json");
+    JsonExporter::default().write_export(&json, shortpaths_default()).unwrap();
+    assert_eq!(mode(&json) & 0o111, 0, "Data exports are not made executable");
+
+    let bookmarks = root.join("bookmarks");
+    write(&bookmarks, "").unwrap();
+    set_permissions(&bookmarks, Permissions::from_mode(0o600)).unwrap();
+    GtkBookmarksExporter.write_export(&bookmarks, shortpaths_default()).unwrap();
+    assert_eq!(mode(&bookmarks), 0o600, "Updated files keep their permissions");
+
+    set_permissions(&script, Permissions::from_mode(0o640)).unwrap();
+    BashExporter.write_export(&script, shortpaths_default()).unwrap();
+    assert_eq!(mode(&script), 0o640, "Updated shell scripts keep their permissions");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_direnv_project_block() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-direnv");
+    let _ = remove_dir_all(&root);
+    create_dir_all(root.join("proj/src")).unwrap();
+    create_dir_all(root.join("other")).unwrap();
+
+    let sp = indexmap!{
+        "base".to_owned() => Shortpath::new(root.clone(), None),
+        "proj".to_owned() => Shortpath::new(PathBuf::from("$base/proj"), None),
+        "src".to_owned() => Shortpath::new(PathBuf::from("$proj/src"), None),
+        "other".to_owned() => Shortpath::new(PathBuf::from("$base/other"), None),
+    };
+    let shortpaths = setup_shortpaths(|| sp.clone());
+    let envrc = root.join("proj/.envrc");
+    write(&envrc, "use nix\n").unwrap();
+
+    DirenvExporter::default().write_export(&envrc, shortpaths.clone()).unwrap();
+    let expect = format!(concat!(
+        "use nix\n\n",
+        "# >>> shortpath >>>\n",
+        "export base=\"{}\"\n",
+        "export proj=\"$base/proj\"\n",
+        "export src=\"$proj/src\"\n",
+        "# <<< shortpath <<<\n",
+    ), root.display());
+    assert_eq!(read_to_string(&envrc).unwrap(), expect, "Only the project shortpaths and their aliases are exported");
+
+    let mut shortpaths = shortpaths;
+    shortpaths.shift_remove("src");
+    write(&envrc, format!("{}dotenv\n", read_to_string(&envrc).unwrap())).unwrap();
+    DirenvExporter::default().write_export(&envrc, shortpaths).unwrap();
+    let expect = format!(concat!(
+        "use nix\n\n",
+        "# >>> shortpath >>>\n",
+        "export base=\"{}\"\n",
+        "export proj=\"$base/proj\"\n",
+        "# <<< shortpath <<<\n",
+        "dotenv\n",
+    ), root.display());
+    assert_eq!(read_to_string(&envrc).unwrap(), expect, "The block is updated in place, leaving the user content untouched");
+
+    write(&envrc, b"use nix\n# \xff\n").unwrap();
+    assert!(DirenvExporter::default().write_export(&envrc, sp).is_err(), "Unreadable .envrc files are an error");
+    assert_eq!(std::fs::read(&envrc).unwrap(), b"use nix\n# \xff\n", "Unreadable .envrc files are left untouched");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_update_marked_block() {
+    enable_logging();
+    let block = "# >>> shortpath >>>\nexport a=\"/a\"\n# <<< shortpath <<<\n";
+    assert_eq!(update_marked_block("", block), block, "New files only have the block");
+    assert_eq!(update_marked_block("use nix", block), format!("use nix\n\n{}", block));
+    assert_eq!(update_marked_block("a\n# >>> shortpath >>>\nold\n# <<< shortpath <<<\nb", block), format!("a\n{}b", block),
+        "Content around the block is kept, without adding a trailing newline");
+    assert_eq!(update_marked_block("# >>> shortpath >>>\nunterminated\n", block), format!("# >>> shortpath >>>\nunterminated\n\n{}", block),
+        "Blocks without an end marker are not replaced");
+}
+
+#[test]
+fn test_gtk_bookmarks_sync() {
+    enable_logging();
+    let root = temp_dir().join("shortpath-bookmarks");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let bookmarks = root.join("bookmarks");
+    write(&bookmarks, "file:///home/user/Music\nfile:///srv/shared Shared\n").unwrap();
+
+    let shortpaths: SP = indexmap!{
+        "proj".to_owned() => Shortpath::new(PathBuf::from("/srv/my proj"), Some(PathBuf::from("/srv/my proj"))),
+        "shared".to_owned() => Shortpath::new(PathBuf::from("/srv/shared"), Some(PathBuf::from("/srv/shared"))),
+        "broken".to_owned() => Shortpath::new(PathBuf::from("$missing"), None),
+    };
+    GtkBookmarksExporter.write_export(&bookmarks, shortpaths).unwrap();
+    assert_eq!(read_to_string(&bookmarks).unwrap(), "file:///home/user/Music\nfile:///srv/shared Shared\nfile:///srv/my%20proj proj\n",
+        "Shortpaths are bookmarked, unless the user already bookmarked them");
+
+    let shortpaths: SP = indexmap!{
+        "code".to_owned() => Shortpath::new(PathBuf::from("/srv/code"), Some(PathBuf::from("/srv/code"))),
+    };
+    GtkBookmarksExporter.write_export(&bookmarks, shortpaths).unwrap();
+    assert_eq!(read_to_string(&bookmarks).unwrap(), "file:///home/user/Music\nfile:///srv/shared Shared\nfile:///srv/code code\n",
+        "Bookmarks created by shortpath are replaced, and the user's bookmarks are kept");
+
+    let managed = root.join("bookmarks.shortpath");
+    write(&managed, b"\xff\n").unwrap();
+    assert!(GtkBookmarksExporter.write_export(&bookmarks, SP::new()).is_err(), "Unreadable records are an error");
+    assert_eq!(read_to_string(&bookmarks).unwrap(), "file:///home/user/Music\nfile:///srv/shared Shared\nfile:///srv/code code\n",
+        "Bookmarks are left untouched when the record can't be read");
+    remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_gtk_bookmarks_uri() {
+    enable_logging();
+    assert_eq!(file_uri(Path::new("/a b/%/ü/@x")), "file:///a%20b/%25/%C3%BC/@x");
+    assert_eq!(file_uri(Path::new(OsStr::from_bytes(b"/\xFF"))), "file:///%FF", "Paths that aren't valid UTF-8 can be bookmarked");
+
+    let managed = vec![String::from("file:///old old")];
+    let (output, added) = sync_bookmarks("file:///old old\nfile:///mine\n", &managed, &[String::from("file:///mine mine")]);
+    assert_eq!(output, "file:///mine\n");
+    assert!(added.is_empty(), "The user's bookmarks are never taken over");
+
+    let managed = vec![String::from("file:///code code")];
+    let (output, added) = sync_bookmarks("file:///code code\nfile:///mine\nfile:///code code\n", &managed, &[]);
+    assert_eq!(output, "file:///code code\nfile:///mine\n", "A user's bookmark identical to one shortpath created is kept");
+    assert!(added.is_empty());
+}
+
+#[test]
+fn test_user_dirs_export() {
+    enable_logging();
+    let home = Path::new("/home/user");
+    let shortpaths: SP = indexmap!{
+        "documents".to_owned() => Shortpath::new(PathBuf::from("/home/user/Docs $1"), Some(PathBuf::from("/home/user/Docs $1"))),
+        "Music".to_owned() => Shortpath::new(PathBuf::from("/mnt/music"), Some(PathBuf::from("/mnt/music"))),
+        "proj".to_owned() => Shortpath::new(PathBuf::from("/srv/proj"), Some(PathBuf::from("/srv/proj"))),
+    };
+    let existing = "# Written by xdg-user-dirs-update\nXDG_DESKTOP_DIR=\"$HOME/Desktop\"\nXDG_DOCUMENTS_DIR=\"$HOME/Documents\"\n";
+    let entries = user_dir_entries(&shortpaths, home, false);
+    let expect = concat!(
+        "# Written by xdg-user-dirs-update\n",
+        "XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n",
+        "XDG_DOCUMENTS_DIR=\"$HOME/Docs \\$1\"\n",
+    );
+    assert_eq!(update_user_dirs(existing, &entries), expect, "Only exact names set user directories by default");
+
+    let entries = user_dir_entries(&shortpaths, home, true);
+    let expect = concat!(
+        "# Written by xdg-user-dirs-update\n",
+        "XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n",
+        "XDG_DOCUMENTS_DIR=\"$HOME/Docs \\$1\"\n",
+        "XDG_MUSIC_DIR=\"/mnt/music\"\n",
+    );
+    assert_eq!(update_user_dirs(existing, &entries), expect, "Only the user directories named by shortpaths are changed");
+    assert!(UserDirsExporter::default().gen_export(SP::new()).is_empty(), "Shortpaths that aren't user directories are not exported");
+
+    let record = record_user_dirs("", existing, &entries);
+    assert_eq!(record, "XDG_DOCUMENTS_DIR=\"$HOME/Documents\"\n", "The replaced entries are recorded");
+    let updated = update_user_dirs(existing, &entries);
+    assert_eq!(record_user_dirs(&record, &updated, &entries), record, "Only the first value of each entry is recorded");
+
+    let root = temp_dir().join("shortpath-user-dirs");
+    let _ = remove_dir_all(&root);
+    create_dir_all(&root).unwrap();
+    let user_dirs = root.join("user-dirs.dirs");
+    write(&user_dirs, b"XDG_MUSIC_DIR=\"/mnt/\xff\"\n").unwrap();
+    assert!(UserDirsExporter::default().write_export(&user_dirs, shortpaths).is_err(), "Unreadable user-dirs.dirs files are an error");
+    assert_eq!(std::fs::read(&user_dirs).unwrap(), b"XDG_MUSIC_DIR=\"/mnt/\xff\"\n", "Unreadable user-dirs.dirs files are left untouched");
+    assert!(!root.join("user-dirs.shortpath").exists(), "Nothing is recorded");
+    remove_dir_all(root).unwrap();
+}
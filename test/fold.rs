@@ -0,0 +1,56 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::ShortpathsBuilder,
+    env::EP,
+    shortpaths::{FoldAlias, Shortpath, SP, find_fold, fold_shortpath, fold_shortpath_for},
+};
+
+use std::path::PathBuf;
+
+use indexmap::indexmap;
+
+fn setup_aliases() -> SP {
+    let sp = indexmap! {
+        "home".to_owned() => Shortpath::new(PathBuf::from("/home/user"), None),
+        "a".to_owned() => Shortpath::new(PathBuf::from("/home/a"), None),
+        "proj".to_owned() => Shortpath::new(PathBuf::from("$home/proj"), None),
+        "project".to_owned() => Shortpath::new(PathBuf::from("$home/proj"), None),
+    };
+    ShortpathsBuilder::from(sp).build().unwrap().shortpaths
+}
+
+#[test]
+fn test_fold_longest_prefix() {
+    enable_logging();
+    let shortpaths = setup_aliases();
+
+    let fold = |path: &str| fold_shortpath(PathBuf::from(path), &shortpaths);
+    assert_eq!(fold("/home/user/docs"), PathBuf::from("$home/docs"));
+    assert_eq!(fold("/home/user/proj/src"), PathBuf::from("$proj/src"), "The most specific alias is used");
+    assert_eq!(fold("/home/ab/src"), PathBuf::from("/home/ab/src"), "Prefixes are matched per component");
+    assert_eq!(fold("/home/user"), PathBuf::from("/home/user"), "Paths are not folded into themselves");
+    assert_eq!(fold("$home/docs"), PathBuf::from("$home/docs"));
+}
+
+#[test]
+fn test_fold_priority() {
+    enable_logging();
+    let shortpaths = setup_aliases();
+    let env: EP = indexmap! {
+        "HOME".to_owned() => "/home/user".to_owned(),
+        "PWD".to_owned() => "/home/user/proj/src".to_owned(),
+        "CODE".to_owned() => "/home/user/proj/src/code".to_owned(),
+    };
+
+    let (alias, rest) = find_fold(&PathBuf::from("/home/user/docs"), &shortpaths, Some(&env), None).unwrap();
+    assert_eq!(alias, FoldAlias::Shortpath("home".to_owned()), "Aliases are preferred over environment variables");
+    assert_eq!(rest, PathBuf::from("docs"));
+
+    let fold = |path: &str| fold_shortpath_for(None, PathBuf::from(path), &shortpaths, Some(&env));
+    assert_eq!(fold("/home/user/proj/src/main.rs"), PathBuf::from("$proj/src/main.rs"), "Volatile variables are not used");
+    assert_eq!(fold("/home/user/proj/src/code/lib"), PathBuf::from("${env:CODE}/lib"), "Longer prefixes win over aliases");
+
+    let fold = |name: &str, path: &str| fold_shortpath_for(Some(name), PathBuf::from(path), &shortpaths, None);
+    assert_eq!(fold("home", "/home/user/proj/x"), PathBuf::from("/home/user/proj/x"), "Aliases that depend on the shortpath are skipped");
+}
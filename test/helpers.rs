@@ -1,13 +1,15 @@
-use shortpaths::{
+use shortpath::{
     shortpaths::{Shortpath, SP},
     builder:: ShortpathsBuilder,
+    resolve::ResolveConfig,
 };
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use indexmap::indexmap;
 use log::LevelFilter;
 use pretty_env_logger::formatted_timed_builder;
+use tempfile::TempDir;
 
 // Different shortpath configurations to choose from
 pub fn shortpaths_default() -> SP {
@@ -42,6 +44,7 @@ pub fn enable_logging() {
 }
 
 /// Enables all log statements (with color) for a single test only
+#[allow(dead_code)]
 pub fn enable_logging_single_test() {
     formatted_timed_builder().filter_level(LevelFilter::Trace).init();
 }
@@ -53,3 +56,13 @@ pub fn setup_shortpaths(get_shortpaths: impl Fn() -> SP) -> SP {
         ShortpathsBuilder::from(sp_paths).build().unwrap();
     paths .shortpaths
 }
+
+/// Create a unique temporary directory for a test, which is removed when dropped
+pub fn test_dir(test_name: &str) -> TempDir {
+    tempfile::Builder::new().prefix(&format!("shortpath-{}-", test_name)).tempdir().unwrap()
+}
+
+/// A resolve config that uses an empty trash under `root` instead of the user's trash
+pub fn test_resolve_config(root: &Path) -> ResolveConfig {
+    ResolveConfig { trash_dir: Some(root.join("Trash")), ..Default::default() }
+}
@@ -0,0 +1,103 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::{Shortpaths, ShortpathsBuilder},
+    shortpaths::{Shortpath, update_shortpath},
+};
+
+use std::path::PathBuf;
+
+use indexmap::indexmap;
+
+fn setup_chain() -> Shortpaths {
+    let sp = indexmap! {
+        "a".to_owned() => Shortpath::new(PathBuf::from("/a"), None),
+        "b".to_owned() => Shortpath::new(PathBuf::from("$a/b"), None),
+        "c".to_owned() => Shortpath::new(PathBuf::from("$b/c"), None),
+        "other".to_owned() => Shortpath::new(PathBuf::from("/other"), None),
+    };
+    ShortpathsBuilder::from(sp).build().unwrap()
+}
+
+/// Check the incrementally expanded paths against a full rebuild
+fn assert_rebuilt(paths: &Shortpaths) {
+    let rebuilt = ShortpathsBuilder::from(paths.shortpaths.to_owned()).build().unwrap();
+    for (name, sp) in rebuilt.shortpaths.iter() {
+        assert_eq!(paths.shortpaths[name].full_path, sp.full_path, "{} is expanded as if rebuilt", name);
+    }
+}
+
+#[test]
+fn test_incremental_update() {
+    enable_logging();
+    let mut paths = setup_chain();
+    assert_eq!(paths.dependents("a").into_iter().collect::<Vec<_>>(), vec!["b", "c"]);
+
+    let reexpanded = paths.update_path("a", PathBuf::from("/moved"));
+    assert_eq!(reexpanded, vec!["a", "b", "c"], "Only the dependents are re-expanded");
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("/moved/b/c")));
+    assert_rebuilt(&paths);
+
+    let reexpanded = paths.update_path("b", PathBuf::from("$other/b"));
+    assert_eq!(reexpanded, vec!["b", "c"]);
+    assert!(paths.dependents("a").is_empty());
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("/other/b/c")));
+    assert_rebuilt(&paths);
+}
+
+#[test]
+fn test_incremental_broken_aliases() {
+    enable_logging();
+    let mut paths = setup_chain();
+
+    paths.update_path("a", PathBuf::from("$c/a"));
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("$b/c")), "Cycles are left unexpanded");
+    assert_rebuilt(&paths);
+
+    paths.update_path("a", PathBuf::from("/a"));
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("/a/b/c")), "Breaking the cycle expands it again");
+
+    let (removed, reexpanded) = paths.remove_path("a");
+    assert_eq!(removed.unwrap().path, PathBuf::from("/a"));
+    assert_eq!(reexpanded, vec!["b", "c"]);
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("$b/c")), "Undefined aliases are left unexpanded");
+    assert_rebuilt(&paths);
+
+    paths.update_path("a", PathBuf::from("/new"));
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("/new/b/c")), "Defining an alias expands its dependents");
+    assert_rebuilt(&paths);
+}
+
+#[test]
+fn test_incremental_after_set_shortpaths() {
+    enable_logging();
+    let mut paths = setup_chain();
+
+    // Replace the shortpaths, like the CLI does with a copy of them
+    let mut shortpaths = paths.shortpaths.to_owned();
+    shortpaths.insert("d".to_owned(), Shortpath::new(PathBuf::from("$c/d"), None));
+    shortpaths.insert("b".to_owned(), Shortpath::new(PathBuf::from("$other/b"), None));
+    paths.set_shortpaths(shortpaths);
+
+    assert!(paths.dependents("a").is_empty(), "Dependents follow replaced shortpaths");
+    let reexpanded = paths.update_path("other", PathBuf::from("/moved"));
+    assert_eq!(reexpanded, vec!["other", "b", "c", "d"]);
+    assert_eq!(paths.shortpaths["d"].full_path, Some(PathBuf::from("/moved/b/c/d")));
+    assert_rebuilt(&paths);
+}
+
+#[test]
+fn test_incremental_update_shortpath() {
+    enable_logging();
+    let mut paths = setup_chain();
+
+    update_shortpath(&mut paths, "a", None, Some(PathBuf::from("/moved")));
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("/moved/b/c")), "Updating a path re-expands its dependents");
+    assert_rebuilt(&paths);
+
+    update_shortpath(&mut paths, "a", Some("renamed".to_owned()), None);
+    assert_eq!(paths.shortpaths["renamed"].full_path, Some(PathBuf::from("/moved")));
+    assert_eq!(paths.shortpaths["c"].full_path, Some(PathBuf::from("$b/c")), "Renaming an alias leaves its dependents unexpanded");
+    assert_eq!(paths.dependents("renamed").len(), 0);
+    assert_rebuilt(&paths);
+}
@@ -1,4 +1,15 @@
 mod helpers;
+mod incremental;
+mod lint;
 mod shortpaths;
+mod check;
+mod escape;
 mod exports;
+mod fold;
+mod picker;
+mod refactor;
+mod git;
+mod resolve;
+mod roundtrip;
+mod trash;
 mod log_enable;
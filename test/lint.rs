@@ -0,0 +1,74 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    app::ExportType,
+    builder::ShortpathsBuilder,
+    lint::{LintFix, LintKind, fix_lints, lint_name, lint_shortpaths, normalize_parent_dirs},
+    shortpaths::Shortpath,
+};
+
+use std::path::PathBuf;
+
+use indexmap::indexmap;
+
+#[test]
+fn test_lint_names() {
+    enable_logging();
+    assert!(lint_name("my_proj").is_empty());
+
+    let lints = lint_name("my-proj");
+    assert_eq!(lints[0].kind, LintKind::InvalidName(ExportType::Bash));
+    assert_eq!(lints[0].fix, Some(LintFix::Rename("my_proj".to_owned())));
+    assert!(lints.iter().all(|lint| lint.is_dangerous()));
+
+    let lints = lint_name("PATH");
+    assert_eq!(lints.len(), 1);
+    assert_eq!(lints[0].kind, LintKind::ShadowsEnvVar, "Well known environment variables are not clobbered");
+    assert_eq!(lints[0].fix, None);
+}
+
+#[test]
+fn test_lint_paths() {
+    enable_logging();
+    assert_eq!(normalize_parent_dirs(&PathBuf::from("/a/b/../c")), PathBuf::from("/a/c"));
+    assert_eq!(normalize_parent_dirs(&PathBuf::from("$a/../c")), PathBuf::from("$a/../c"), "Aliases are not expanded");
+
+    let sp = indexmap! {
+        "base".to_owned() => Shortpath::new(PathBuf::from("/tmp/base"), None),
+        "sub".to_owned() => Shortpath::new(PathBuf::from("/tmp/base/sub/"), None),
+        "up".to_owned() => Shortpath::new(PathBuf::from("/tmp/other/../up"), None),
+        "rel".to_owned() => Shortpath::new(PathBuf::from("relative/dir"), None),
+    };
+    let mut shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let lints = lint_shortpaths(&shortpaths);
+    let kinds: Vec<(&str, &LintKind)> = lints.iter().map(|lint| (lint.name.as_str(), &lint.kind)).collect();
+    assert_eq!(kinds, vec![
+        ("sub", &LintKind::TrailingSlash),
+        ("sub", &LintKind::UnfoldedPrefix("base".to_owned())),
+        ("up", &LintKind::ParentDir),
+        ("rel", &LintKind::RelativePath),
+    ]);
+
+    fix_lints(&mut shortpaths, &lints);
+    assert_eq!(shortpaths["sub"].path, PathBuf::from("$base/sub"));
+    assert_eq!(shortpaths["up"].path, PathBuf::from("/tmp/up"));
+    assert_eq!(shortpaths["rel"].path, PathBuf::from("relative/dir"), "Relative paths can't be fixed");
+}
+
+#[test]
+fn test_lint_fix_rename() {
+    enable_logging();
+    let sp = indexmap! {
+        "my-proj".to_owned() => Shortpath::new(PathBuf::from("/tmp/proj"), None),
+        "src".to_owned() => Shortpath::new(PathBuf::from("$my-proj/src"), None),
+    };
+    let mut shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let lints = lint_shortpaths(&shortpaths);
+    fix_lints(&mut shortpaths, &lints);
+    assert!(shortpaths.contains_key("my_proj"));
+    assert!(!shortpaths.contains_key("my-proj"));
+    assert_eq!(shortpaths["src"].path, PathBuf::from("$my_proj/src"), "Dependents use the renamed alias");
+    assert!(lint_shortpaths(&shortpaths).is_empty());
+}
@@ -0,0 +1,93 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    picker::{Picker, PickerEvent},
+    resolve::{Candidates, ResolveAction, ResolveCandidate, ResolveEntry, ResolveStrategy},
+    shortpaths::{Shortpath, SP},
+};
+
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use indexmap::indexmap;
+
+fn candidate(path: &str, score: f64) -> ResolveCandidate {
+    ResolveCandidate { full_path: PathBuf::from(path), score, strategy: ResolveStrategy::Matching, action: ResolveAction::Update }
+}
+
+fn setup_picker() -> Picker {
+    let unreachable: SP = indexmap! {
+        "docs".to_owned() => Shortpath::new(PathBuf::from("/old/docs"), None),
+        "music".to_owned() => Shortpath::new(PathBuf::from("/old/music"), None),
+    };
+    let candidates: Candidates = indexmap! {
+        "docs".to_owned() => vec![candidate("/new/docs", 0.5), candidate("/backup/docs", 0.33)],
+        "music".to_owned() => vec![candidate("/new/music", 0.5)],
+    };
+    Picker::new(&unreachable, &candidates)
+}
+
+fn press(picker: &mut Picker, code: KeyCode) -> PickerEvent {
+    picker.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+#[test]
+fn test_picker_choose_in_bulk() {
+    enable_logging();
+    let mut picker = setup_picker();
+
+    press(&mut picker, KeyCode::Down);
+    press(&mut picker, KeyCode::Enter);
+    assert_eq!(picker.selected, 1, "Choosing a candidate moves on to the next shortpath");
+    assert!(picker.choices().contains_key("docs"));
+
+    press(&mut picker, KeyCode::Enter);
+    let event = picker.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert_eq!(event, PickerEvent::Apply);
+
+    let choices = picker.choices();
+    assert_eq!(choices["docs"].full_path, PathBuf::from("/backup/docs"));
+    assert_eq!(choices["music"].full_path, PathBuf::from("/new/music"));
+
+    press(&mut picker, KeyCode::Delete);
+    assert!(!picker.choices().contains_key("music"), "Choices can be cleared to skip a shortpath");
+}
+
+#[test]
+fn test_picker_fuzzy_filter() {
+    enable_logging();
+    let mut picker = setup_picker();
+    assert_eq!(picker.filtered(), vec![0, 1], "Candidates are shown in ranked order");
+
+    "bkp".chars().for_each(|c| { press(&mut picker, KeyCode::Char(c)); });
+    assert_eq!(picker.filtered(), vec![1], "Candidates are fuzzy filtered");
+    assert_eq!(picker.highlighted().unwrap().full_path, PathBuf::from("/backup/docs"));
+
+    assert_eq!(press(&mut picker, KeyCode::Esc), PickerEvent::Continue, "Escape clears the filter first");
+    assert_eq!(picker.filtered(), vec![0, 1]);
+    assert_eq!(press(&mut picker, KeyCode::Esc), PickerEvent::Cancel);
+}
+
+#[test]
+fn test_picker_descendants() {
+    enable_logging();
+    let rewrite = |name: &str| ResolveEntry {
+        name: name.to_owned(),
+        previous: PathBuf::from(format!("/old/proj/{}", name)),
+        updated: PathBuf::from(format!("$proj/{}", name)),
+        full_path: PathBuf::from(format!("/new/proj/{}", name)),
+        score: 1.0,
+        strategy: ResolveStrategy::Descendant,
+        action: ResolveAction::Update,
+    };
+    let descendants = indexmap! { "proj".to_owned() => vec![rewrite("src"), rewrite("docs")] };
+    let mut picker = Picker::descendants(&descendants);
+    assert_eq!(picker.title, "Descendants");
+    assert_eq!(picker.choices().len(), 2, "Every rewrite is chosen by default");
+    assert_eq!(picker.highlighted().unwrap().full_path, PathBuf::from("/new/proj/src"));
+
+    press(&mut picker, KeyCode::Delete);
+    let choices = picker.choices();
+    assert!(!choices.contains_key("src"), "Rewrites can be skipped");
+    assert_eq!(choices["docs"].strategy, ResolveStrategy::Descendant);
+}
@@ -0,0 +1,56 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::ShortpathsBuilder,
+    refactor::{apply_refactor, find_shared_dirs, plan_refactor},
+    shortpaths::Shortpath,
+};
+
+use std::path::PathBuf;
+
+use indexmap::indexmap;
+
+#[test]
+fn test_refactor_shared_dirs() {
+    enable_logging();
+    let sp = indexmap! {
+        "x".to_owned() => Shortpath::new(PathBuf::from("/srv/work/proj/x"), None),
+        "y".to_owned() => Shortpath::new(PathBuf::from("/srv/work/proj/y"), None),
+        "z".to_owned() => Shortpath::new(PathBuf::from("/srv/work/other/z"), None),
+        "lone".to_owned() => Shortpath::new(PathBuf::from("/opt/lone"), None),
+    };
+    let mut shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let shared: Vec<PathBuf> = find_shared_dirs(&shortpaths).into_keys().collect();
+    assert_eq!(shared, vec![PathBuf::from("/srv/work"), PathBuf::from("/srv/work/proj")],
+        "Only the deepest directory of every group is shared");
+
+    let refactor = plan_refactor(&shortpaths);
+    assert_eq!(refactor.added["work"].path, PathBuf::from("/srv/work"));
+    assert_eq!(refactor.added["proj"].path, PathBuf::from("$work/proj"), "Base aliases are nested");
+    assert_eq!(refactor.rewritten["x"].1, PathBuf::from("$proj/x"));
+    assert_eq!(refactor.rewritten["z"].1, PathBuf::from("$work/other/z"));
+    assert!(!refactor.rewritten.contains_key("lone"));
+    assert!(refactor.to_diff().contains("- x = /srv/work/proj/x\n+ x = $proj/x\n"));
+
+    apply_refactor(&mut shortpaths, &refactor);
+    let shortpaths = ShortpathsBuilder::from(shortpaths).build().unwrap().shortpaths;
+    assert_eq!(shortpaths["x"].full_path, Some(PathBuf::from("/srv/work/proj/x")), "Refactoring keeps the expanded paths");
+    assert!(plan_refactor(&shortpaths).is_empty());
+}
+
+#[test]
+fn test_refactor_reuses_aliases() {
+    enable_logging();
+    let sp = indexmap! {
+        "proj".to_owned() => Shortpath::new(PathBuf::from("/srv/proj"), None),
+        "a".to_owned() => Shortpath::new(PathBuf::from("/srv/proj/a"), None),
+        "b".to_owned() => Shortpath::new(PathBuf::from("/srv/proj/b"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let refactor = plan_refactor(&shortpaths);
+    assert!(refactor.added.is_empty(), "Existing aliases of shared directories are reused");
+    assert_eq!(refactor.rewritten["a"].1, PathBuf::from("$proj/a"));
+    assert_eq!(refactor.rewritten["b"].1, PathBuf::from("$proj/b"));
+}
@@ -0,0 +1,209 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test, test_dir, test_resolve_config};
+use shortpath::{
+    git::{GitRepo, Repos},
+    app::{ResolveType, Mode},
+    builder::ShortpathsBuilder,
+    helpers::{index_scopes, merge_scopes},
+    resolve::{ResolveConfig, ResolvePlan, ResolveStrategy},
+    shortpaths::{Shortpath, SP, plan_resolve, apply_resolve_plan},
+};
+
+use std::{
+    ffi::OsString,
+    fs::{create_dir_all, remove_dir_all, write},
+    path::PathBuf,
+};
+
+use indexmap::{indexmap, IndexSet};
+use tempfile::TempDir;
+
+/// Create a directory that was moved from `old/things` to `things`
+fn setup_moved_dir(test_name: &str) -> (TempDir, PathBuf, SP) {
+    let dir = test_dir(test_name);
+    let root = dir.path().to_owned();
+    create_dir_all(root.join("things")).unwrap();
+
+    let moved = indexmap! {
+        "things".to_owned() => Shortpath::new(root.join("old/things"), None),
+    };
+    let paths = ShortpathsBuilder::from(moved).build().unwrap();
+    (dir, root, paths.shortpaths)
+}
+
+#[test]
+fn test_resolve_plan_does_not_modify() {
+    enable_logging();
+    let (_dir, root, shortpaths) = setup_moved_dir("plan");
+
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &test_resolve_config(&root));
+    assert_eq!(plan.entries.len(), 1, "Finds the moved directory");
+
+    let entry = &plan.entries[0];
+    assert_eq!(entry.name, "things");
+    assert_eq!(entry.previous, root.join("old/things"));
+    assert_eq!(entry.full_path, root.join("things"));
+    assert_eq!(entry.score, 0.5, "Candidates in ancestor directories score lower");
+    assert_eq!(entry.strategy, ResolveStrategy::Matching);
+    assert_eq!(shortpaths["things"].path, root.join("old/things"), "Planning leaves the shortpaths untouched");
+}
+
+#[test]
+fn test_resolve_plan_apply_json() {
+    enable_logging();
+    let (_dir, root, mut shortpaths) = setup_moved_dir("apply");
+
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &test_resolve_config(&root));
+    let plan_file = root.join("plan.json");
+    write(&plan_file, plan.to_json()).unwrap();
+    let plan = ResolvePlan::read(&plan_file).unwrap();
+
+    let applied = apply_resolve_plan(&mut shortpaths, &plan);
+    assert_eq!(applied.len(), 1, "Plans can be applied after being reviewed");
+    assert_eq!(shortpaths["things"].path, root.join("things"));
+    assert_eq!(shortpaths["things"].full_path, Some(root.join("things")));
+
+    let applied = apply_resolve_plan(&mut shortpaths, &plan);
+    assert!(applied.is_empty(), "Stale plans are not applied");
+
+    write(&plan_file, "{").unwrap();
+    assert!(ResolvePlan::read(&plan_file).is_err(), "Invalid plans are reported instead of panicking");
+    assert!(ResolvePlan::read(&root.join("missing.json")).is_err());
+}
+
+#[test]
+fn test_resolve_undefined_aliases() {
+    enable_logging();
+    let (_dir, root, mut shortpaths) = setup_moved_dir("undefined");
+    shortpaths.insert("broken".to_owned(), Shortpath::new(PathBuf::from("$missing/broken"), None));
+    shortpaths.insert("a".to_owned(), Shortpath::new(PathBuf::from("$b/a"), None));
+    shortpaths.insert("b".to_owned(), Shortpath::new(PathBuf::from("$a/b"), None));
+    let repos: Repos = indexmap! {
+        "broken".to_owned() => GitRepo { remote: "https://example.com/broken.git".to_owned(), root_commit: None },
+    };
+
+    let cfg = ResolveConfig { roots: vec![root.clone()], ..test_resolve_config(&root) };
+    let plan = plan_resolve(&shortpaths, &repos, ResolveType::Matching, Mode::Automatic, &cfg);
+    assert_eq!(plan.entries.len(), 1, "Undefined and cyclic aliases have no candidates");
+    assert_eq!(plan.entries[0].name, "things");
+}
+
+#[test]
+fn test_resolve_search_roots() {
+    enable_logging();
+    let (_dir, root, shortpaths) = setup_moved_dir("roots");
+    remove_dir_all(root.join("things")).unwrap();
+    create_dir_all(root.join("deep/nested/things")).unwrap();
+
+    let plan_with = |cfg: ResolveConfig| plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &cfg);
+
+    let plan = plan_with(test_resolve_config(&root));
+    assert!(plan.is_empty(), "Only parent directories are searched without search roots");
+
+    let cfg = ResolveConfig { roots: vec![root.clone()], ..test_resolve_config(&root) };
+    let plan = plan_with(cfg.clone());
+    assert_eq!(plan.entries.len(), 1, "Search roots are searched recursively");
+    assert_eq!(plan.entries[0].full_path, root.join("deep/nested/things"));
+
+    let plan = plan_with(ResolveConfig { max_depth: Some(2), ..cfg.clone() });
+    assert!(plan.is_empty(), "Search roots are not searched beyond max_depth");
+
+    let plan = plan_with(ResolveConfig { exclude: vec![PathBuf::from("nested")], ..cfg.clone() });
+    assert!(plan.is_empty(), "Excluded directory names are not searched");
+
+    let plan = plan_with(ResolveConfig { exclude: vec![PathBuf::from("deep/nested")], ..cfg.clone() });
+    assert!(plan.is_empty(), "Excluded relative paths are not searched");
+
+    let plan = plan_with(ResolveConfig { exclude: vec![PathBuf::from("other/nested")], ..cfg.clone() });
+    assert_eq!(plan.entries.len(), 1, "Relative paths only match whole components");
+
+    let plan = plan_with(ResolveConfig { exclude: vec![root.join("deep")], ..cfg });
+    assert!(plan.is_empty(), "Excluded paths are not searched");
+}
+
+#[test]
+fn test_resolve_shared_search_index() {
+    enable_logging();
+    let dir = test_dir("shared");
+    let root = dir.path().to_owned();
+    create_dir_all(root.join("new/one")).unwrap();
+    create_dir_all(root.join("new/two")).unwrap();
+
+    let unreachable = indexmap! {
+        "one".to_owned() => Shortpath::new(root.join("old/one"), None),
+        "two".to_owned() => Shortpath::new(root.join("old/two"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(unreachable).build().unwrap().shortpaths;
+    let cfg = ResolveConfig { roots: vec![root.clone()], ..test_resolve_config(&root) };
+
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &cfg);
+    let found: Vec<(&str, PathBuf)> = plan.entries.iter()
+        .map(|entry| (entry.name.as_str(), entry.full_path.to_owned()))
+        .collect();
+    assert_eq!(found, vec![("one", root.join("new/one")), ("two", root.join("new/two"))],
+        "Every unreachable shortpath is matched against the same search index");
+}
+
+#[test]
+fn test_resolve_index_merges_scopes() {
+    enable_logging();
+    let dir = test_dir("merge-scopes");
+    let root = dir.path().to_owned();
+    create_dir_all(root.join("a/one")).unwrap();
+    create_dir_all(root.join("a/b/one")).unwrap();
+    create_dir_all(root.join("a/other")).unwrap();
+
+    let scopes: IndexSet<(PathBuf, Option<usize>)> = IndexSet::from([
+        (root.join("a/b"), Some(1)),
+        (root.clone(), Some(3)),
+        (root.join("a"), Some(1)),
+        (root.join("a"), None),
+    ]);
+    assert_eq!(merge_scopes(&scopes), IndexSet::from([(root.clone(), Some(3)), (root.join("a"), None)]),
+        "Nested scopes are walked by the scopes they are in");
+
+    let wanted = IndexSet::from([OsString::from("one")]);
+    let index = index_scopes(scopes, &wanted, &test_resolve_config(&root));
+    let found = |scope: (PathBuf, Option<usize>)| -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = index[&scope].iter().map(|file| file.path().to_owned()).collect();
+        files.sort();
+        files
+    };
+    assert_eq!(found((root.join("a/b"), Some(1))), vec![root.join("a/b/one")]);
+    assert_eq!(found((root.join("a"), Some(1))), vec![root.join("a/one")],
+        "A nested scope keeps its own depth");
+    assert_eq!(found((root.clone(), Some(3))), vec![root.join("a/b/one"), root.join("a/one")],
+        "Only the wanted file names are indexed");
+}
+
+#[test]
+fn test_resolve_descendants() {
+    enable_logging();
+    let dir = test_dir("descendants");
+    let root = dir.path().to_owned();
+    create_dir_all(root.join("new/proj/src")).unwrap();
+
+    let sp = indexmap! {
+        "proj".to_owned() => Shortpath::new(root.join("old/proj"), None),
+        "src".to_owned() => Shortpath::new(root.join("old/proj/src"), None),
+        "docs".to_owned() => Shortpath::new(PathBuf::from("$proj/docs"), None),
+    };
+    let mut shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+    let mut cfg = ResolveConfig { roots: vec![root.join("new")], ..test_resolve_config(&root) };
+
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &cfg);
+    let src = plan.entries.iter().find(|entry| entry.name == "src").unwrap();
+    assert_eq!(src.strategy, ResolveStrategy::Matching, "Descendants are only rewritten automatically when asked to");
+
+    cfg.rewrite_descendants = true;
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &cfg);
+    let src = plan.entries.iter().find(|entry| entry.name == "src").unwrap();
+    assert_eq!(src.strategy, ResolveStrategy::Descendant, "Literal paths under a moved shortpath follow it");
+    assert_eq!(src.updated, PathBuf::from("$proj/src"));
+    assert_eq!(src.full_path, root.join("new/proj/src"));
+    assert!(plan.entries.iter().all(|entry| entry.name != "docs"), "Aliased paths are left as is");
+
+    apply_resolve_plan(&mut shortpaths, &plan);
+    assert_eq!(shortpaths["proj"].full_path, Some(root.join("new/proj")));
+    assert_eq!(shortpaths["src"].path, PathBuf::from("$proj/src"));
+}
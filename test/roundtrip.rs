@@ -0,0 +1,121 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test};
+use shortpath::{
+    builder::ShortpathsBuilder,
+    shortpaths::{AliasError, Shortpath, SP, expand_shortpath, expand_shortpaths, fold_shortpath},
+};
+
+use std::path::PathBuf;
+
+use indexmap::indexmap;
+use proptest::prelude::*;
+
+/// Path components, including ones that look like aliases
+const COMPONENTS: &[&str] = &["x", "y", "a1", "a10", "$a1", "$a10", "$", "~", ".."];
+
+fn expand(path: &PathBuf, shortpaths: &SP) -> PathBuf {
+    expand_shortpath(&Shortpath::new(path.to_owned(), None), shortpaths).unwrap()
+}
+
+fn components(max: usize) -> impl Strategy<Value = Vec<&'static str>> {
+    prop::collection::vec(prop::sample::select(COMPONENTS), 0..max)
+}
+
+/// Generate an acyclic alias graph, where every alias is either a literal path
+/// or depends on an alias defined before it
+fn alias_graph() -> impl Strategy<Value = SP> {
+    prop::collection::vec((any::<bool>(), any::<prop::sample::Index>(), components(4)), 1..12)
+        .prop_map(|aliases| {
+            let sp: SP = aliases.iter().enumerate().map(|(i, (literal, dep, comps))| {
+                let base = match (literal, i) {
+                    (true, _) | (_, 0) => String::from("/base"),
+                    (false, i) => format!("$a{}", dep.index(i)),
+                };
+                let path = comps.iter().fold(PathBuf::from(base), |path, comp| path.join(comp));
+                (format!("a{}", i), Shortpath::new(path, None))
+            }).collect();
+            ShortpathsBuilder::from(sp).build().unwrap().shortpaths
+        })
+}
+
+/// Generate absolute paths, most of them under one of the aliases
+fn path_in(shortpaths: SP) -> impl Strategy<Value = (SP, PathBuf)> {
+    let len = shortpaths.len();
+    (any::<bool>(), 0..len, components(4)).prop_map(move |(under_alias, i, comps)| {
+        let base = match under_alias {
+            true => shortpaths[i].full_path.to_owned().unwrap(),
+            false => PathBuf::from("/"),
+        };
+        let path = comps.iter().fold(base, |path, comp| path.join(comp));
+        (shortpaths.to_owned(), path)
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_roundtrip_fold_expand((shortpaths, path) in alias_graph().prop_flat_map(path_in)) {
+        let folded = fold_shortpath(path.clone(), &shortpaths);
+        prop_assert_eq!(expand(&folded, &shortpaths), path);
+    }
+
+    #[test]
+    fn test_roundtrip_expand_all(shortpaths in alias_graph()) {
+        let expanded = expand_shortpaths(&shortpaths);
+        for (name, sp) in shortpaths.iter() {
+            prop_assert_eq!(&expanded[name], &expand_shortpath(sp, &shortpaths).ok());
+        }
+    }
+}
+
+#[test]
+fn test_expand_all_broken_aliases() {
+    enable_logging();
+    let sp = indexmap! {
+        "a".to_owned() => Shortpath::new(PathBuf::from("$b/a"), None),
+        "b".to_owned() => Shortpath::new(PathBuf::from("$a/b"), None),
+        "c".to_owned() => Shortpath::new(PathBuf::from("$a/c"), None),
+        "d".to_owned() => Shortpath::new(PathBuf::from("$missing/d"), None),
+        "e".to_owned() => Shortpath::new(PathBuf::from("/e"), None),
+    };
+    let expanded = expand_shortpaths(&sp);
+    assert_eq!(expanded["a"], None, "Cyclic aliases are not expanded");
+    assert_eq!(expanded["b"], None);
+    assert_eq!(expanded["c"], None, "Shortpaths that depend on a cycle are not expanded");
+    assert_eq!(expanded["d"], None, "Undefined aliases are not expanded");
+    assert_eq!(expanded["e"], Some(PathBuf::from("/e")));
+
+    assert_eq!(expand_shortpath(&sp["a"], &sp), Err(AliasError::Cycle(vec!["b".to_owned(), "a".to_owned(), "b".to_owned()])),
+        "Expanding a single cyclic shortpath stops at the cycle");
+    assert_eq!(expand_shortpath(&sp["d"], &sp), Err(AliasError::Undefined("missing".to_owned())));
+    assert_eq!(expand_shortpath(&sp["e"], &sp), Ok(PathBuf::from("/e")));
+}
+
+#[test]
+fn test_roundtrip_alias_like_components() {
+    enable_logging();
+    let sp = indexmap! {
+        "a".to_owned() => Shortpath::new(PathBuf::from("/base"), None),
+        "ab".to_owned() => Shortpath::new(PathBuf::from("$a/ab"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    let path = PathBuf::from("/base/ab/$a/$ab");
+    let folded = fold_shortpath(path.clone(), &shortpaths);
+    assert_eq!(folded, PathBuf::from("$ab/$a/$ab"));
+    assert_eq!(expand(&folded, &shortpaths), path, "Only the first component is expanded");
+}
+
+#[test]
+fn test_roundtrip_unfoldable_names() {
+    enable_logging();
+    let sp = indexmap! {
+        "a/b".to_owned() => Shortpath::new(PathBuf::from("/base"), None),
+        "{env:HOME}".to_owned() => Shortpath::new(PathBuf::from("/other"), None),
+    };
+    let shortpaths = ShortpathsBuilder::from(sp).build().unwrap().shortpaths;
+
+    for path in ["/base/x", "/other/x"].map(PathBuf::from) {
+        let folded = fold_shortpath(path.clone(), &shortpaths);
+        assert_eq!(folded, path, "Names that can't be expanded again are not folded into");
+    }
+}
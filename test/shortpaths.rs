@@ -4,9 +4,11 @@ use crate::helpers::{
     shortpaths_default,
     setup_shortpaths,
 };
-use shortpaths::{
+use shortpath::{
+    git::Repos,
     app::{ResolveType, Mode},
     builder::ShortpathsBuilder,
+    resolve::ResolveConfig,
     shortpaths::{
         FindKeyIndexMapExt,
         Shortpath, resolve,
@@ -51,7 +53,7 @@ fn test_shortpaths_resolve_auto() {
     let mode = Mode::Automatic;
     let dry_run = true;
     
-    resolve(&mut shortpaths, resolve_type, mode, dry_run);
+    resolve(&mut shortpaths, &Repos::new(), resolve_type, mode, &ResolveConfig::default(), dry_run);
     //assert_eq!(1, 0, "Show debug output");
 }
 
@@ -74,6 +76,6 @@ fn test_shortpaths_resolve_manual() {
     let mode = Mode::Manual;
     let dry_run = true;
     
-    resolve(&mut shortpaths, resolve_type, mode, dry_run);
+    resolve(&mut shortpaths, &Repos::new(), resolve_type, mode, &ResolveConfig::default(), dry_run);
     //assert_eq!(1, 0, "Show debug output");
 }
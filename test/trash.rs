@@ -0,0 +1,107 @@
+#[allow(unused_imports)]
+use crate::helpers::{enable_logging, enable_logging_single_test, test_dir, test_resolve_config};
+use shortpath::{
+    git::Repos,
+    app::{ResolveType, Mode},
+    builder::ShortpathsBuilder,
+    resolve::{ResolveAction, ResolveConfig, ResolveEntry, ResolvePlan, ResolveStrategy},
+    shortpaths::{Shortpath, SP, plan_resolve, apply_resolve_plan, find_candidates, find_unreachable},
+    trash::{parse_trash_info, percent_decode, read_trash, read_trash_info, find_trashed},
+};
+
+use std::{
+    fs::{create_dir_all, write},
+    path::PathBuf,
+};
+
+use indexmap::indexmap;
+
+#[test]
+fn test_parse_trash_info() {
+    enable_logging();
+    assert_eq!(percent_decode("/home/user/my%20dir"), PathBuf::from("/home/user/my dir"));
+    assert_eq!(percent_decode("/home/user/100%"), PathBuf::from("/home/user/100%"), "Invalid escapes are kept as is");
+
+    let conts = "[Trash Info]\nPath=/home/user/my%20dir\nDeletionDate=2023-01-15T10:30:00\n";
+    let (path, deleted) = parse_trash_info(conts).unwrap();
+    assert_eq!(path, PathBuf::from("/home/user/my dir"));
+    assert_eq!(deleted.unwrap().to_string(), "2023-01-15 10:30:00");
+
+    assert_eq!(parse_trash_info("[Trash Info]\nPath=relative/dir\n"), None, "Relative paths are ignored");
+    assert_eq!(parse_trash_info("Path=/home/user/dir\n"), None, "Files without the header are ignored");
+}
+
+#[test]
+fn test_resolve_restore_trashed() {
+    enable_logging();
+    let dir = test_dir("trash");
+    let root = dir.path().to_owned();
+    let (trash, original) = (root.join("Trash"), root.join("home/things"));
+    create_dir_all(trash.join("info")).unwrap();
+    create_dir_all(trash.join("files/things")).unwrap();
+    let info = format!("[Trash Info]\nPath={}\nDeletionDate=2023-01-15T10:30:00\n", original.display());
+    write(trash.join("info/things.trashinfo"), info).unwrap();
+
+    let entries = read_trash(&trash);
+    let trashed = find_trashed(&original, &entries).unwrap();
+    assert_eq!(trashed.deleted_on(), "2023-01-15 10:30:00");
+    assert_eq!(read_trash_info(&trash, &trashed.info).as_ref(), Some(trashed), "Prompts read the deletion date from the info file");
+
+    let unreachable = indexmap! {
+        "things".to_owned() => Shortpath::new(original.clone(), None),
+    };
+    let mut shortpaths = ShortpathsBuilder::from(unreachable).build().unwrap().shortpaths;
+    let cfg = ResolveConfig { trash_dir: Some(trash.clone()), ..test_resolve_config(&root) };
+
+    let plan = plan_resolve(&shortpaths, &Repos::new(), ResolveType::Matching, Mode::Automatic, &cfg);
+    assert!(plan.is_empty(), "Trashed shortpaths are never restored automatically");
+
+    let candidates = find_candidates(&find_unreachable(&shortpaths), &Repos::new(), ResolveType::Matching, &cfg);
+    let restore = &candidates["things"][0];
+    assert_eq!(restore.strategy, ResolveStrategy::Trash, "Trashed shortpaths are found in the trash");
+    assert!(matches!(restore.action, ResolveAction::Restore { .. }));
+
+    // Choose to restore it, as in manual mode
+    let mut plan = ResolvePlan::new();
+    plan.push(ResolveEntry {
+        name: "things".to_owned(),
+        previous: shortpaths["things"].path.to_owned(),
+        updated: shortpaths["things"].path.to_owned(),
+        full_path: restore.full_path.to_owned(),
+        score: restore.score,
+        strategy: restore.strategy,
+        action: restore.action.to_owned(),
+    });
+    let applied = apply_resolve_plan(&mut shortpaths, &plan);
+    assert_eq!(applied.len(), 1);
+    assert!(original.exists(), "Trashed shortpaths are restored");
+    assert!(read_trash(&trash).is_empty(), "Restored files are removed from the trash");
+}
+
+#[test]
+fn test_resolve_remove_trashed() {
+    enable_logging();
+    let mut shortpaths: SP = indexmap! {
+        "a".to_owned() => Shortpath::new(PathBuf::from("/a"), None),
+        "things".to_owned() => Shortpath::new(PathBuf::from("/trashed/things"), None),
+        "src".to_owned() => Shortpath::new(PathBuf::from("$things/src"), None),
+        "same".to_owned() => Shortpath::new(PathBuf::from("$things"), None),
+        "z".to_owned() => Shortpath::new(PathBuf::from("/z"), None),
+    };
+    let mut plan = ResolvePlan::new();
+    plan.push(ResolveEntry {
+        name: "things".to_owned(),
+        previous: PathBuf::from("/trashed/things"),
+        updated: PathBuf::from("/trashed/things"),
+        full_path: PathBuf::from("/trashed/things"),
+        score: 1.0,
+        strategy: ResolveStrategy::Trash,
+        action: ResolveAction::Remove,
+    });
+
+    apply_resolve_plan(&mut shortpaths, &plan);
+    let names: Vec<&str> = shortpaths.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["a", "src", "same", "z"], "Removing a shortpath keeps the order of the others");
+    assert_eq!(shortpaths["src"].path, PathBuf::from("/trashed/things/src"), "Dependents keep the path of the removed alias");
+    assert_eq!(shortpaths["same"].path, PathBuf::from("/trashed/things"));
+}